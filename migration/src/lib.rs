@@ -1,12 +1,26 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20261019_000001_create_audit_event_table;
+mod m20261019_000002_add_user_disabled_at;
+mod m20261019_000003_create_rate_limit_bucket_table;
+mod m20261019_000004_add_version_columns;
+mod m20261019_000005_add_timestamps;
+mod m20261019_000006_create_idempotency_key_table;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20261019_000001_create_audit_event_table::Migration),
+            Box::new(m20261019_000002_add_user_disabled_at::Migration),
+            Box::new(m20261019_000003_create_rate_limit_bucket_table::Migration),
+            Box::new(m20261019_000004_add_version_columns::Migration),
+            Box::new(m20261019_000005_add_timestamps::Migration),
+            Box::new(m20261019_000006_create_idempotency_key_table::Migration),
+        ]
     }
 }
@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::{
+    integer_null, pk_auto, string, string_null, timestamp_with_time_zone,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // No foreign key on `actor_id`: audit rows must outlive the users they mention.
+        manager
+            .create_table(
+                Table::create()
+                    .table("audit_event")
+                    .if_not_exists()
+                    .col(pk_auto("id"))
+                    .col(integer_null("actor_id"))
+                    .col(string("action"))
+                    .col(string_null("target"))
+                    .col(string_null("ip"))
+                    .col(string_null("user_agent"))
+                    .col(string("result"))
+                    .col(timestamp_with_time_zone("created_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_event_created_at")
+                    .table("audit_event")
+                    .col("created_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_event_actor_id")
+                    .table("audit_event")
+                    .col("actor_id")
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table("audit_event").to_owned())
+            .await?;
+        Ok(())
+    }
+}
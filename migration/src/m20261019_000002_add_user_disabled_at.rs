@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::timestamp_with_time_zone_null;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A disabled account keeps its rows (audit trail, roles) but can no longer log in.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("user")
+                    .add_column(timestamp_with_time_zone_null("disabled_at"))
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table("user")
+                    .drop_column("disabled_at")
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::{boolean, double, string, timestamp_with_time_zone};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Only used with `rate_limit.store = "database"`, rows are purged once full again.
+        manager
+            .create_table(
+                Table::create()
+                    .table("rate_limit_bucket")
+                    .if_not_exists()
+                    .col(string("key").primary_key())
+                    .col(double("tokens"))
+                    .col(boolean("allowed"))
+                    .col(timestamp_with_time_zone("updated_at"))
+                    .col(timestamp_with_time_zone("expires_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_rate_limit_bucket_expires_at")
+                    .table("rate_limit_bucket")
+                    .col("expires_at")
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table("rate_limit_bucket").to_owned())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::integer;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Tables whose rows carry a `version`, bumped on every update for optimistic concurrency.
+const VERSIONED_TABLES: [&str; 2] = ["user", "role"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in VERSIONED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(integer("version").default(1))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in VERSIONED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column("version")
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::timestamp_with_time_zone;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const TRACKED_TABLES: [&str; 3] = ["user", "role", "permission"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing rows get the migration date, new ones are stamped by `before_save`.
+        for table in TRACKED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(
+                            timestamp_with_time_zone("created_at")
+                                .default(Expr::current_timestamp()),
+                        )
+                        .add_column(
+                            timestamp_with_time_zone("updated_at")
+                                .default(Expr::current_timestamp()),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in TRACKED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column("created_at")
+                        .drop_column("updated_at")
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::schema::{
+    blob_null, integer_null, string, text_null, timestamp_with_time_zone,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A row without `status` is a request still running, rows are purged once expired.
+        manager
+            .create_table(
+                Table::create()
+                    .table("idempotency_key")
+                    .if_not_exists()
+                    .col(string("key").primary_key())
+                    .col(string("fingerprint"))
+                    .col(integer_null("status"))
+                    .col(text_null("headers"))
+                    .col(blob_null("body"))
+                    .col(timestamp_with_time_zone("created_at").default(Expr::current_timestamp()))
+                    .col(timestamp_with_time_zone("expires_at"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_idempotency_key_expires_at")
+                    .table("idempotency_key")
+                    .col("expires_at")
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table("idempotency_key").to_owned())
+            .await?;
+        Ok(())
+    }
+}
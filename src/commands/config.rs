@@ -0,0 +1,16 @@
+use anyhow::Result;
+
+use crate::utils::cfg::Config;
+use crate::utils::cli::ConfigCommand;
+
+/// `Config::load` already failed with every problem if the configuration is invalid,
+/// so reaching this point means it is valid.
+pub fn run(config: &Config, command: ConfigCommand) -> Result<()> {
+    match command {
+        ConfigCommand::Check => {
+            println!("# The configuration is valid, effective values:");
+            print!("{}", toml::to_string_pretty(&config.redacted())?);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::DatabaseConnection;
+
+use crate::utils::cfg::Config;
+use crate::utils::cli::{MigrateAction, MigrateArgs};
+use crate::utils::db::connect_with_retry;
+use crate::utils::migrations::{rollback_migrations, run_migrations};
+
+pub async fn run(config: &Config, args: MigrateArgs) -> Result<()> {
+    let db = connect_with_retry(&config.database, &config.database.url, "primary").await?;
+
+    let result = match args.action.unwrap_or_default() {
+        MigrateAction::Up => run_migrations(&db).await.map_err(Into::into),
+        MigrateAction::Status => print_status(&db).await,
+        MigrateAction::Down { steps } => rollback_migrations(&db, steps).await.map_err(Into::into),
+    };
+    db.close().await?;
+    result
+}
+
+async fn print_status(db: &DatabaseConnection) -> Result<()> {
+    for migration in Migrator::get_applied_migrations(db).await? {
+        println!("applied  {}", migration.name());
+    }
+    for migration in Migrator::get_pending_migrations(db).await? {
+        println!("pending  {}", migration.name());
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use anyhow::{Context, Result};
+use sea_orm::DatabaseConnection;
+
+use crate::modules::types::ServiceResult;
+use crate::modules::user::dto::UserDto;
+use crate::modules::user::service::UserService;
+use crate::utils::cfg::Config;
+use crate::utils::db::connect_with_retry;
+use crate::utils::migrations::apply_migration_mode;
+
+pub mod config;
+pub mod migrate;
+pub mod role;
+pub mod session;
+pub mod user;
+
+/// Connects like `serve` does, so the admin commands never run against an outdated schema.
+async fn connect(config: &Config) -> Result<DatabaseConnection> {
+    let db = connect_with_retry(&config.database, &config.database.url, "primary").await?;
+    apply_migration_mode(&db, config.database.migration_mode).await?;
+    Ok(db)
+}
+
+/// Users are designated by id or by email on the command line.
+async fn resolve_user(db: &DatabaseConnection, user: &str) -> ServiceResult<UserDto> {
+    let user_svc = UserService::new(db);
+    match user.parse::<i32>() {
+        Ok(id) => user_svc.get_one(id).await,
+        Err(_) => user_svc
+            .get_per_email_model(&user.to_lowercase())
+            .await
+            .map(UserDto::from),
+    }
+}
+
+/// Reads the password from stdin unless given as a flag, which would leak it in `ps`.
+fn read_password(password: Option<String>) -> Result<String> {
+    if let Some(password) = password {
+        return Ok(password);
+    }
+
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+        io::stderr().flush()?;
+    }
+    let mut password = String::new();
+    stdin
+        .lock()
+        .read_line(&mut password)
+        .context("Cannot read the password from stdin")?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_owned())
+}
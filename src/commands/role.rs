@@ -0,0 +1,47 @@
+use anyhow::Result;
+use validator::Validate;
+
+use crate::commands::{connect, resolve_user};
+use crate::modules::audit::domain::AuditContext;
+use crate::modules::user::payload::RolePayload;
+use crate::modules::user::service::UserService;
+use crate::utils::cfg::Config;
+use crate::utils::cli::RoleCommand;
+
+pub async fn run(config: &Config, command: RoleCommand) -> Result<()> {
+    let db = connect(config).await?;
+
+    let result = async {
+        let ctx = AuditContext::cli();
+        let user_svc = UserService::new(&db);
+        match command {
+            RoleCommand::Grant {
+                user,
+                role,
+                description,
+            } => {
+                let payload = RolePayload {
+                    name: role,
+                    description,
+                };
+                payload.validate()?;
+
+                let user = resolve_user(&db, &user).await?;
+                let role = user_svc.grant_role(user.id, payload, &ctx).await?;
+                println!(
+                    "Granted '{}' to user {} <{}>",
+                    role.name, user.id, user.email
+                );
+            }
+            RoleCommand::Revoke { user, role } => {
+                let user = resolve_user(&db, &user).await?;
+                user_svc.revoke_role(user.id, &role, &ctx).await?;
+                println!("Revoked '{}' from user {} <{}>", role, user.id, user.email);
+            }
+        }
+        anyhow::Ok(())
+    }
+    .await;
+    db.close().await?;
+    result
+}
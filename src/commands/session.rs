@@ -0,0 +1,30 @@
+use anyhow::Result;
+
+use crate::commands::{connect, resolve_user};
+use crate::modules::audit::domain::AuditContext;
+use crate::modules::session::service::SessionService;
+use crate::utils::cfg::Config;
+use crate::utils::cli::SessionCommand;
+
+pub async fn run(config: &Config, command: SessionCommand) -> Result<()> {
+    let db = connect(config).await?;
+
+    let result = async {
+        match command {
+            SessionCommand::Revoke { user } => {
+                let user = resolve_user(&db, &user).await?;
+                let revoked = SessionService::new(&db)
+                    .revoke_all_for_user(user.id, &AuditContext::cli())
+                    .await?;
+                println!(
+                    "Revoked {} session(s) of user {} <{}>",
+                    revoked, user.id, user.email
+                );
+            }
+        }
+        anyhow::Ok(())
+    }
+    .await;
+    db.close().await?;
+    result
+}
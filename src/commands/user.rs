@@ -0,0 +1,124 @@
+use anyhow::Result;
+use sea_orm::DatabaseConnection;
+use validator::Validate;
+
+use crate::commands::{connect, read_password, resolve_user};
+use crate::modules::audit::domain::AuditContext;
+use crate::modules::user::domain::ADMIN_ROLE;
+use crate::modules::user::dto::UserDto;
+use crate::modules::user::payload::{CreateUser, ResetPasswordPayload, RolePayload, UserListQuery};
+use crate::modules::user::service::UserService;
+use crate::utils::cfg::Config;
+use crate::utils::cli::UserCommand;
+
+pub async fn run(config: &Config, command: UserCommand) -> Result<()> {
+    let db = connect(config).await?;
+
+    let result = match command {
+        UserCommand::Create {
+            name,
+            email,
+            password,
+            admin,
+        } => {
+            let payload = CreateUser {
+                name,
+                email: email.to_lowercase(),
+                password: read_password(password)?,
+            };
+            create(&db, payload, admin).await
+        }
+        UserCommand::List => list(&db).await,
+        UserCommand::Disable { user } => disable(&db, &user).await,
+        UserCommand::ResetPassword { user, password } => {
+            let payload = ResetPasswordPayload {
+                password: read_password(password)?,
+            };
+            reset_password(&db, &user, payload).await
+        }
+    };
+    db.close().await?;
+    result
+}
+
+async fn create(db: &DatabaseConnection, payload: CreateUser, admin: bool) -> Result<()> {
+    payload.validate()?;
+
+    let ctx = AuditContext::cli();
+    let user_svc = UserService::new(db);
+    let user = user_svc.create(payload, &ctx).await?;
+    if admin {
+        let admin_role = RolePayload {
+            name: ADMIN_ROLE.to_owned(),
+            description: Some("Granted from the command line".to_owned()),
+        };
+        user_svc.grant_role(user.id, admin_role, &ctx).await?;
+    }
+
+    println!(
+        "Created user {} <{}> with id {}",
+        user.name, user.email, user.id
+    );
+    Ok(())
+}
+
+async fn list(db: &DatabaseConnection) -> Result<()> {
+    let user_svc = UserService::new(db);
+    for user in user_svc.get_all(&UserListQuery::default()).await? {
+        let roles: Vec<String> = user_svc
+            .get_roles(user.id)
+            .await?
+            .into_iter()
+            .map(|role| role.name)
+            .collect();
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            user.id,
+            user.email,
+            user.name,
+            if roles.is_empty() {
+                "-".to_owned()
+            } else {
+                roles.join(",")
+            },
+            status(&user)
+        );
+    }
+    Ok(())
+}
+
+fn status(user: &UserDto) -> String {
+    match user.disabled_at {
+        Some(disabled_at) => format!("disabled since {}", disabled_at.to_rfc3339()),
+        None => "active".to_owned(),
+    }
+}
+
+async fn disable(db: &DatabaseConnection, user: &str) -> Result<()> {
+    let user = resolve_user(db, user).await?;
+    let user = UserService::new(db)
+        .disable(user.id, &AuditContext::cli())
+        .await?;
+
+    println!("User {} <{}> is {}", user.id, user.email, status(&user));
+    Ok(())
+}
+
+async fn reset_password(
+    db: &DatabaseConnection,
+    user: &str,
+    payload: ResetPasswordPayload,
+) -> Result<()> {
+    payload.validate()?;
+
+    let user = resolve_user(db, user).await?;
+    UserService::new(db)
+        .reset_password(user.id, payload, &AuditContext::cli())
+        .await?;
+
+    println!(
+        "Password of user {} <{}> reset, its sessions were revoked",
+        user.id, user.email
+    );
+    Ok(())
+}
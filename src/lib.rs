@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use aide::openapi::OpenApi;
+use axum::extract::{DefaultBodyLimit, MatchedPath, Request};
+use axum::{Router, middleware, routing::get};
+use opentelemetry::global;
+use opentelemetry_http::HeaderExtractor;
+use tower::ServiceBuilder;
+use tower_http::catch_panic::CatchPanicLayer;
+use tower_http::compression::CompressionLayer;
+use tower_http::decompression::RequestDecompressionLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use tracing::info_span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::modules::compression::middleware::reject_unsupported_encoding;
+use crate::modules::fallback::middleware::handle_panic;
+use crate::modules::fallback::route::{handle_method_not_allowed, handle_not_found};
+use crate::modules::idempotency::middleware::handle_idempotency_key;
+use crate::modules::limits::middleware::enforce_limits;
+use crate::modules::metrics::middleware::track_http_metrics;
+use crate::modules::openapi::route::{api_docs, api_router, openapi_router};
+use crate::modules::rate_limit::middleware::rate_limit;
+use crate::modules::responses::scope_error_details;
+use crate::modules::security::cors::cors_layer;
+use crate::modules::security::headers::{SecurityHeaders, add_security_headers};
+use crate::modules::states::AppState;
+use crate::utils::request_id::{X_REQUEST_ID, scope_request_id};
+
+pub mod commands;
+pub mod modules;
+pub mod utils;
+
+/// The public API with every middleware, configured from `app_state.config`. `serve`
+/// runs it on the listener, the integration tests call it in process.
+pub fn build_app(app_state: AppState) -> Router {
+    let config = app_state.config.clone();
+
+    let mut api = OpenApi::default();
+    let api_routes = api_router().finish_api_with(&mut api, api_docs);
+
+    let limits = Arc::new(config.limits.clone());
+    let security_headers = Arc::new(SecurityHeaders::new(&config.security));
+
+    let mut app = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .merge(api_routes)
+        .merge(openapi_router(&api))
+        .fallback(handle_not_found)
+        .method_not_allowed_fallback(handle_method_not_allowed)
+        .with_state(app_state.clone())
+        // Inside of the limits, the body it buffers is capped and the timeout covers it.
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            handle_idempotency_key,
+        ))
+        .layer(middleware::from_fn_with_state(limits, enforce_limits))
+        // Outside of the limits, so they apply to the decompressed body.
+        .layer(RequestDecompressionLayer::new())
+        .layer(middleware::from_fn(reject_unsupported_encoding))
+        .layer(DefaultBodyLimit::disable())
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit,
+        ))
+        .layer(middleware::from_fn(track_http_metrics));
+    if let Some(cors) = cors_layer(&config.cors) {
+        app = app.layer(cors);
+    }
+    app.layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::new(X_REQUEST_ID, MakeRequestUuid))
+            .layer(TraceLayer::new_for_http().make_span_with(make_request_span))
+            .layer(PropagateRequestIdLayer::new(X_REQUEST_ID))
+            .layer(CompressionLayer::new())
+            .layer(middleware::from_fn(scope_request_id))
+            .layer(middleware::from_fn_with_state(
+                app_state,
+                scope_error_details,
+            ))
+            .layer(middleware::from_fn_with_state(
+                security_headers,
+                add_security_headers,
+            ))
+            .layer(CatchPanicLayer::custom(handle_panic)),
+    )
+}
+
+/// Root span of a request, continuing the caller's trace when a `traceparent` header is sent.
+fn make_request_span(request: &Request) -> tracing::Span {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or("unmatched");
+    let request_id = request
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let span = info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        http.route = route,
+        request_id,
+    );
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(request.headers()))
+    });
+    span.set_parent(parent).ok();
+    span
+}
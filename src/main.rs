@@ -1,51 +1,225 @@
 #[global_allocator]
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use anyhow::Error;
 use anyhow::Result;
-use axum::{Router, routing::get};
-use tower_http::trace::TraceLayer;
-use tracing::info;
-
-use crate::modules::auth::route::auth_router;
-use crate::modules::states::AppState;
-use crate::modules::user::route::user_router;
-use crate::utils::cfg::Config;
-
-pub mod modules;
-pub mod utils;
+use axum_server_poc::build_app;
+use axum_server_poc::commands;
+use axum_server_poc::modules::idempotency::service::IdempotencyKeys;
+use axum_server_poc::modules::metrics::recorder::install_recorder;
+use axum_server_poc::modules::metrics::route::metrics_router;
+use axum_server_poc::modules::rate_limit::service::RateLimiter;
+use axum_server_poc::modules::states::AppState;
+use axum_server_poc::modules::tls::listener::{IntoMakeTlsService, TlsListener};
+use axum_server_poc::modules::tls::redirect::redirect_router;
+use axum_server_poc::modules::tls::reload::ReloadableTls;
+use axum_server_poc::modules::user::domain::X_SETUP_TOKEN;
+use axum_server_poc::modules::user::service::UserService;
+use axum_server_poc::utils::cfg::Config;
+use axum_server_poc::utils::cli::{Cli, Command};
+use axum_server_poc::utils::db::connect_with_retry;
+use axum_server_poc::utils::logging::LogFilterHandle;
+use axum_server_poc::utils::migrations::apply_migration_mode;
+use axum_server_poc::utils::shutdown::{BackgroundTasks, shutdown_signal};
+use axum_server_poc::utils::telemetry::init_tracing;
+use clap::Parser;
+use sea_orm::DatabaseConnection;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    use migration::{Migrator, MigratorTrait};
+    let cli = Cli::parse();
+    let config = Config::load(&cli.config)?;
+
+    let telemetry = init_tracing(&config)?;
+
+    let result = match cli.command.unwrap_or_default() {
+        Command::Serve => serve(config, telemetry.log_filter).await,
+        Command::Migrate(args) => commands::migrate::run(&config, args).await,
+        Command::User(command) => commands::user::run(&config, command).await,
+        Command::Role(command) => commands::role::run(&config, command).await,
+        Command::Session(command) => commands::session::run(&config, command).await,
+        Command::Config(command) => commands::config::run(&config, command),
+    };
+
+    if let Some(tracer_provider) = telemetry.tracer_provider
+        && let Err(err) = tracer_provider.shutdown()
+    {
+        warn!("Failed to flush pending spans: {}", err);
+    }
+    result
+}
+
+async fn serve(config: Config, log_filter: LogFilterHandle) -> Result<(), Error> {
+    let connection =
+        Arc::new(connect_with_retry(&config.database, &config.database.url, "primary").await?);
+    apply_migration_mode(&connection, config.database.migration_mode).await?;
+
+    let setup_token = setup_token(&connection, &config).await?;
+    let mut app_state = AppState::new(Arc::new(config.clone()), connection, log_filter)
+        .with_setup_token(setup_token);
+    if let Some(replica_url) = &config.database.replica_url {
+        let replica = connect_with_retry(&config.database, replica_url, "replica").await?;
+        app_state = app_state.with_read_replica(Arc::new(replica));
+    }
+
+    let background = BackgroundTasks::new();
 
-    let config = Config::new();
+    if config.rate_limit.enabled {
+        let rate_limiter = Arc::new(RateLimiter::from_config(
+            &config.rate_limit,
+            app_state.connection.clone(),
+        ));
+        background.spawn(|shutdown| rate_limiter.clone().purge_expired(shutdown));
+        app_state = app_state.with_rate_limiter(rate_limiter);
+    }
 
-    tracing_subscriber::fmt()
-        .with_max_level(config.log_level)
-        .init();
+    if config.idempotency.enabled {
+        let idempotency = Arc::new(IdempotencyKeys::new(
+            config.idempotency.clone(),
+            app_state.connection.clone(),
+        ));
+        background.spawn(|shutdown| idempotency.clone().purge_expired(shutdown));
+        app_state = app_state.with_idempotency(idempotency);
+    }
 
-    info!("Trying to connect to database...");
-    let mut opt = sea_orm::ConnectOptions::new(config.database_url);
-    opt.max_connections(2).min_connections(1);
-    let connection = Arc::new(sea_orm::Database::connect(opt).await?);
-    Migrator::up(connection.as_ref(), None).await?;
+    let app = build_app(app_state.clone());
 
-    let app_state = AppState { connection };
+    if let Some(metrics_addr) = &config.metrics.addr {
+        let metrics_app = metrics_router(install_recorder()?).with_state(app_state.clone());
+        let metrics_listener = tokio::net::TcpListener::bind(metrics_addr).await?;
+        info!("Metrics are exposed on http://{}/metrics", metrics_addr);
+        background.spawn(|shutdown| async move {
+            if let Err(err) = axum::serve(metrics_listener, metrics_app)
+                .with_graceful_shutdown(shutdown.cancelled_owned())
+                .await
+            {
+                error!("The metrics listener stopped: {}", err);
+            }
+        });
+    }
 
-    let app = Router::new()
-        .route("/", get(|| async { "Hello, World!" }))
-        .nest("/auth", auth_router())
-        .nest("/users", user_router())
-        .with_state(app_state)
-        .layer(TraceLayer::new_for_http());
+    let tls = if config.tls.enabled {
+        let tls = Arc::new(ReloadableTls::load(&config.tls)?);
+        background.spawn(|shutdown| tls.clone().watch(shutdown));
+        Some(tls)
+    } else {
+        None
+    };
 
-    let target = format!("{}:{}", config.host, config.port);
-    let listener = tokio::net::TcpListener::bind(&target).await.unwrap();
+    if let Some(redirect_port) = config.tls.redirect_http_port {
+        let redirect_target = format!("{}:{}", config.server.host, redirect_port);
+        let redirect_listener = tokio::net::TcpListener::bind(&redirect_target).await?;
+        let redirect_app = redirect_router(config.server.port);
+        info!("Redirecting http://{} to HTTPS", redirect_target);
+        background.spawn(|shutdown| async move {
+            if let Err(err) = axum::serve(redirect_listener, redirect_app)
+                .with_graceful_shutdown(shutdown.cancelled_owned())
+                .await
+            {
+                error!("The HTTPS redirect listener stopped: {}", err);
+            }
+        });
+    }
 
-    info!("The server is running on http://{}", target);
-    axum::serve(listener, app).await.unwrap();
+    let target = format!("{}:{}", config.server.host, config.server.port);
+    let listener = tokio::net::TcpListener::bind(&target).await?;
+
+    let stop_accepting = CancellationToken::new();
+    let mut server = match tls {
+        Some(tls) => {
+            info!("The server is running on https://{}", target);
+            tokio::spawn(
+                axum::serve(
+                    TlsListener::new(listener, tls),
+                    IntoMakeTlsService::new(app),
+                )
+                .with_graceful_shutdown(stop_accepting.clone().cancelled_owned())
+                .into_future(),
+            )
+        }
+        None => {
+            info!("The server is running on http://{}", target);
+            tokio::spawn(
+                axum::serve(
+                    listener,
+                    app.into_make_service_with_connect_info::<SocketAddr>(),
+                )
+                .with_graceful_shutdown(stop_accepting.clone().cancelled_owned())
+                .into_future(),
+            )
+        }
+    };
+
+    tokio::select! {
+        result = &mut server => result??,
+        _ = shutdown_signal() => {
+            app_state.start_draining();
+            let shutdown_delay = config.server.shutdown_delay();
+            if !shutdown_delay.is_zero() {
+                info!(
+                    "Readiness is now failing, closing the listener in {:?}",
+                    shutdown_delay
+                );
+                tokio::time::sleep(shutdown_delay).await;
+            }
+
+            info!(
+                "Draining in-flight requests for at most {:?}...",
+                config.server.shutdown_timeout()
+            );
+            stop_accepting.cancel();
+            match tokio::time::timeout(config.server.shutdown_timeout(), &mut server).await {
+                Ok(result) => result??,
+                Err(_) => {
+                    warn!("Drain timeout elapsed, aborting the remaining requests");
+                    server.abort();
+                }
+            }
+        }
+    }
+
+    background.shutdown(config.server.shutdown_timeout()).await;
+
+    if let Err(err) = app_state.connection.close_by_ref().await {
+        warn!("Failed to close the database connection: {}", err);
+    }
+    if app_state.has_read_replica()
+        && let Err(err) = app_state.read_connection.close_by_ref().await
+    {
+        warn!("Failed to close the replica connection: {}", err);
+    }
+
+    info!("Shutdown complete");
     Ok(())
 }
+
+/// Token expected by the root user bootstrap, only while the instance has no user.
+async fn setup_token(connection: &DatabaseConnection, config: &Config) -> Result<Option<String>> {
+    if UserService::new(connection)
+        .get_activated_users_count()
+        .await?
+        > 0
+    {
+        return Ok(None);
+    }
+
+    let token = match &config.setup.token {
+        Some(token) => token.clone(),
+        None => {
+            let token = Uuid::new_v4().simple().to_string();
+            warn!(
+                "No user exists yet, create the root user with POST /users/initial_root_user \
+                 and the header `{}: {}`",
+                X_SETUP_TOKEN, token
+            );
+            token
+        }
+    };
+    Ok(Some(token))
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+
+use crate::utils::logging::email_digest;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Login,
+    Logout,
+    UserCreate,
+    UserBootstrap,
+    UserUpdate,
+    UserDelete,
+    UserDisable,
+    UserPasswordReset,
+    RoleGrant,
+    RoleRevoke,
+    SessionRevokeAll,
+    LogFilterChange,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Login => "auth.login",
+            Self::Logout => "auth.logout",
+            Self::UserCreate => "user.create",
+            Self::UserBootstrap => "user.bootstrap",
+            Self::UserUpdate => "user.update",
+            Self::UserDelete => "user.delete",
+            Self::UserDisable => "user.disable",
+            Self::UserPasswordReset => "user.password_reset",
+            Self::RoleGrant => "role.grant",
+            Self::RoleRevoke => "role.revoke",
+            Self::SessionRevokeAll => "session.revoke_all",
+            Self::LogFilterChange => "admin.log_filter",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditResult {
+    Success,
+    Failure,
+}
+
+impl AuditResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+        }
+    }
+}
+
+impl<T, E> From<&Result<T, E>> for AuditResult {
+    fn from(result: &Result<T, E>) -> Self {
+        match result {
+            Ok(_) => Self::Success,
+            Err(_) => Self::Failure,
+        }
+    }
+}
+
+/// Target of an event about a user: `user:{id}` once it exists, `email:{digest}` before,
+/// so that the log can be queried per user without storing addresses.
+pub fn user_target(user_id: Option<i32>, email: &str) -> String {
+    match user_id {
+        Some(user_id) => format!("user:{}", user_id),
+        None => format!("email:{}", email_digest(email)),
+    }
+}
+
+/// Who is acting and from where, captured once per request and handed to the services.
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub actor_id: Option<i32>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl AuditContext {
+    /// Admin commands run from the server binary: no actor and no remote address.
+    pub fn cli() -> Self {
+        Self {
+            user_agent: Some(format!("{} cli", env!("CARGO_PKG_NAME"))),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_actor(mut self, actor_id: i32) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+}
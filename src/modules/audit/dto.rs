@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::modules::models::entities::audit_event::Model as AuditEventModel;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AuditEventDto {
+    pub id: i32,
+    pub actor_id: Option<i32>,
+    pub action: String,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub result: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AuditEventModel> for AuditEventDto {
+    fn from(event: AuditEventModel) -> Self {
+        Self {
+            id: event.id,
+            actor_id: event.actor_id,
+            action: event.action,
+            target: event.target,
+            ip: event.ip,
+            user_agent: event.user_agent,
+            result: event.result,
+            created_at: event.created_at.to_utc(),
+        }
+    }
+}
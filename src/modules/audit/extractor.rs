@@ -0,0 +1,37 @@
+use std::convert::Infallible;
+
+use aide::OperationInput;
+use axum::extract::FromRequestParts;
+use axum::http::header::USER_AGENT;
+use axum::http::request::Parts;
+
+use crate::modules::audit::domain::AuditContext;
+use crate::modules::states::AppState;
+use crate::utils::client_ip::client_ip;
+
+pub struct ExtractAuditContext(pub AuditContext);
+
+impl FromRequestParts<AppState> for ExtractAuditContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let ip = client_ip(parts, &state.config.rate_limit);
+
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+
+        Ok(ExtractAuditContext(AuditContext {
+            actor_id: None,
+            ip,
+            user_agent,
+        }))
+    }
+}
+
+impl OperationInput for ExtractAuditContext {}
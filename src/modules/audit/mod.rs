@@ -0,0 +1,6 @@
+pub mod domain;
+pub mod dto;
+pub mod extractor;
+pub mod payload;
+pub mod route;
+pub mod service;
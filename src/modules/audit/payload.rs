@@ -0,0 +1,18 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct AuditQuery {
+    #[validate(range(min = 1, message = "Page must be at least 1"))]
+    pub page: Option<u64>,
+    #[validate(range(min = 1, max = 100, message = "Per page must be between 1 and 100"))]
+    pub per_page: Option<u64>,
+    pub actor_id: Option<i32>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub result: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
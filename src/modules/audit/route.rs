@@ -0,0 +1,35 @@
+use aide::axum::ApiRouter;
+use aide::axum::routing::get_with;
+use axum::extract::State;
+
+use crate::modules::audit::dto::AuditEventDto;
+use crate::modules::audit::payload::AuditQuery;
+use crate::modules::audit::service::AuditService;
+use crate::modules::auth::extractor::ExtractAdminInfos;
+use crate::modules::responses::{ApiError, Negotiated, Paginated};
+use crate::modules::states::AppState;
+use crate::modules::types::ApiResponse;
+use crate::utils::extractor::ExtractValidatedQuery;
+
+pub fn audit_router() -> ApiRouter<AppState> {
+    ApiRouter::new().api_route(
+        "/",
+        get_with(handle_get_audit_events, |op| {
+            op.tag("audit")
+                .summary("List security audit events (admin only)")
+        }),
+    )
+}
+
+async fn handle_get_audit_events(
+    State(state): State<AppState>,
+    ExtractAdminInfos(_): ExtractAdminInfos,
+    ExtractValidatedQuery(query): ExtractValidatedQuery<AuditQuery>,
+) -> ApiResponse<Paginated<AuditEventDto>> {
+    let audit_svc = AuditService::new(&state.read_connection);
+    audit_svc
+        .list(query)
+        .await
+        .map(Negotiated)
+        .map_err(ApiError::from)
+}
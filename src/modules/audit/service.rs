@@ -0,0 +1,96 @@
+use chrono::Utc;
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder,
+};
+use tracing::{Instrument, warn};
+
+use crate::modules::audit::domain::{AuditAction, AuditContext, AuditResult};
+use crate::modules::audit::dto::AuditEventDto;
+use crate::modules::audit::payload::AuditQuery;
+use crate::modules::models::entities::audit_event::ActiveModel as AuditEventActiveModel;
+use crate::modules::models::entities::audit_event::Column as AuditEventColumn;
+use crate::modules::models::entities::audit_event::Entity as AuditEventEntity;
+use crate::modules::responses::Paginated;
+use crate::modules::types::ServiceResult;
+use crate::utils::telemetry::db_span;
+
+const DEFAULT_PER_PAGE: u64 = 20;
+
+pub struct AuditService<'a> {
+    db: &'a DatabaseConnection,
+}
+
+impl<'a> AuditService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Best effort: a failing audit insert is logged but never fails the audited operation.
+    pub async fn record(
+        &self,
+        ctx: &AuditContext,
+        action: AuditAction,
+        target: Option<String>,
+        result: AuditResult,
+    ) {
+        let event = AuditEventActiveModel {
+            actor_id: Set(ctx.actor_id),
+            action: Set(action.as_str().to_owned()),
+            target: Set(target),
+            ip: Set(ctx.ip.clone()),
+            user_agent: Set(ctx.user_agent.clone()),
+            result: Set(result.as_str().to_owned()),
+            created_at: Set(Utc::now().into()),
+            ..Default::default()
+        };
+
+        if let Err(err) = event
+            .insert(self.db)
+            .instrument(db_span("INSERT", "audit_event"))
+            .await
+        {
+            warn!("Failed to record audit event '{}': {}", action, err);
+        }
+    }
+
+    pub async fn list(&self, query: AuditQuery) -> ServiceResult<Paginated<AuditEventDto>> {
+        let page = query.page.unwrap_or(1);
+        let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE);
+
+        let mut select = AuditEventEntity::find();
+        if let Some(actor_id) = query.actor_id {
+            select = select.filter(AuditEventColumn::ActorId.eq(actor_id));
+        }
+        if let Some(action) = query.action {
+            select = select.filter(AuditEventColumn::Action.eq(action));
+        }
+        if let Some(target) = query.target {
+            select = select.filter(AuditEventColumn::Target.eq(target));
+        }
+        if let Some(result) = query.result {
+            select = select.filter(AuditEventColumn::Result.eq(result));
+        }
+        if let Some(from) = query.from {
+            select = select.filter(AuditEventColumn::CreatedAt.gte(from.fixed_offset()));
+        }
+        if let Some(to) = query.to {
+            select = select.filter(AuditEventColumn::CreatedAt.lt(to.fixed_offset()));
+        }
+
+        let paginator = select
+            .order_by_desc(AuditEventColumn::CreatedAt)
+            .order_by_desc(AuditEventColumn::Id)
+            .paginate(self.db, per_page);
+        let total = paginator.num_items().await?;
+        let events = paginator.fetch_page(page - 1).await?;
+
+        Ok(Paginated {
+            items: events.into_iter().map(AuditEventDto::from).collect(),
+            page,
+            per_page,
+            total,
+        })
+    }
+}
@@ -1,8 +1,8 @@
-
-
 use crate::modules::user::dto::UserDto;
 
+#[derive(Clone)]
 pub struct AuthSession {
     pub user: UserDto,
-    pub session_token: Box<str>,
+    /// `None` when the user authenticated with a client certificate.
+    pub session_token: Option<Box<str>>,
 }
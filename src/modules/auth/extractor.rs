@@ -1,12 +1,21 @@
+use aide::OperationInput;
+use aide::generate::GenContext;
+use aide::openapi::{Operation, Response, SecurityRequirement};
 use axum::http::{StatusCode, header::AUTHORIZATION};
 
 use crate::modules::{
-    auth::domain::AuthSession, responses::ApiError, session::service::SessionService,
-    states::AppState,
+    auth::domain::AuthSession, errors::ErrorCode, responses::ApiError,
+    session::service::SessionService, states::AppState, tls::domain::ClientCertificate,
+    user::dto::UserDto, user::service::UserService,
 };
 
 use axum::{extract::FromRequestParts, http::request::Parts};
 
+use crate::utils::extractor::problem_responses;
+
+/// Name of the OpenAPI security scheme describing the `Authorization: Bearer <token>` header.
+pub const BEARER_AUTH: &str = "bearerAuth";
+
 pub struct ExtractAuthInfos(pub AuthSession);
 
 impl FromRequestParts<AppState> for ExtractAuthInfos {
@@ -16,24 +25,39 @@ impl FromRequestParts<AppState> for ExtractAuthInfos {
         parts: &mut Parts,
         app_state: &AppState,
     ) -> Result<Self, Self::Rejection> {
+        // Already resolved by the rate limiter.
+        if let Some(auth_session) = parts.extensions.get::<AuthSession>() {
+            return Ok(ExtractAuthInfos(auth_session.clone()));
+        }
+
+        if !parts.headers.contains_key(AUTHORIZATION)
+            && let Some(client_certificate) = parts.extensions.get::<ClientCertificate>()
+        {
+            let user = certificate_user(client_certificate, app_state).await?;
+            return Ok(ExtractAuthInfos(AuthSession {
+                user,
+                session_token: None,
+            }));
+        }
+
         let authorization = parts.headers.get(AUTHORIZATION).ok_or(ApiError::new(
             StatusCode::UNAUTHORIZED,
-            "`authorization` header is missing".to_owned(),
-            None,
+            ErrorCode::AuthMissingToken,
+            "`authorization` header is missing",
         ))?;
 
         let auth_str = authorization.to_str().map_err(|_| {
             ApiError::new(
                 StatusCode::UNAUTHORIZED,
-                "`authorization` header is malformed".to_owned(),
-                None,
+                ErrorCode::AuthMalformedHeader,
+                "`authorization` header is malformed",
             )
         })?;
 
         let token = auth_str.strip_prefix("Bearer ").ok_or(ApiError::new(
             StatusCode::UNAUTHORIZED,
-            "Invalid authorization scheme".to_owned(),
-            None,
+            ErrorCode::AuthInvalidScheme,
+            "Invalid authorization scheme",
         ))?;
         let token: Box<str> = Box::from(token);
 
@@ -43,23 +67,119 @@ impl FromRequestParts<AppState> for ExtractAuthInfos {
         if !session.is_valid() {
             return Err(ApiError::new(
                 StatusCode::UNAUTHORIZED,
-                "Session is not valid".to_owned(),
-                None,
+                ErrorCode::AuthSessionExpired,
+                "Session is not valid",
             ));
         }
 
-        session
-            .user
-            .ok_or(ApiError::new(
-                StatusCode::UNAUTHORIZED,
-                "The related session has no linked user".to_owned(),
-                None,
-            ))
-            .map(|user_dto| {
-                ExtractAuthInfos(AuthSession {
-                    user: user_dto,
-                    session_token: token,
-                })
-            })
+        let user = session.user.ok_or(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            ErrorCode::AuthSessionInvalid,
+            "The related session has no linked user",
+        ))?;
+
+        if user.is_disabled() {
+            return Err(user_disabled());
+        }
+
+        Ok(ExtractAuthInfos(AuthSession {
+            user,
+            session_token: Some(token),
+        }))
+    }
+}
+
+/// The user whose email is carried by the client certificate verified during the handshake.
+async fn certificate_user(
+    client_certificate: &ClientCertificate,
+    app_state: &AppState,
+) -> Result<UserDto, ApiError> {
+    let unmapped = || {
+        ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            ErrorCode::AuthCertificateUnmapped,
+            format!(
+                "No user matches the client certificate {}",
+                client_certificate.fingerprint
+            ),
+        )
+    };
+    let email = client_certificate.email.as_deref().ok_or_else(unmapped)?;
+
+    let user_service = UserService::new(&app_state.connection);
+    let user = match user_service.get_per_email_model(email).await {
+        Ok(user) => UserDto::from(user),
+        Err(err) if err.status == StatusCode::NOT_FOUND => return Err(unmapped()),
+        Err(err) => return Err(err.into()),
+    };
+    if user.is_disabled() {
+        return Err(user_disabled());
+    }
+    Ok(user)
+}
+
+fn user_disabled() -> ApiError {
+    ApiError::new(
+        StatusCode::FORBIDDEN,
+        ErrorCode::AuthUserDisabled,
+        "This account is disabled",
+    )
+}
+
+pub struct ExtractAdminInfos(pub AuthSession);
+
+impl FromRequestParts<AppState> for ExtractAdminInfos {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        app_state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let ExtractAuthInfos(auth_session) =
+            ExtractAuthInfos::from_request_parts(parts, app_state).await?;
+
+        let user_service = UserService::new(&app_state.connection);
+        if !user_service.is_admin(auth_session.user.id).await? {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                ErrorCode::AuthAdminRequired,
+                "This action requires the admin role",
+            ));
+        }
+
+        Ok(ExtractAdminInfos(auth_session))
+    }
+}
+
+fn require_bearer(operation: &mut Operation) {
+    operation.security.push(SecurityRequirement::from_iter([(
+        BEARER_AUTH.to_owned(),
+        Vec::new(),
+    )]));
+}
+
+impl OperationInput for ExtractAuthInfos {
+    fn operation_input(_ctx: &mut GenContext, operation: &mut Operation) {
+        require_bearer(operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        problem_responses(ctx, operation, &[401, 403])
+    }
+}
+
+impl OperationInput for ExtractAdminInfos {
+    fn operation_input(_ctx: &mut GenContext, operation: &mut Operation) {
+        require_bearer(operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        problem_responses(ctx, operation, &[401, 403])
     }
 }
@@ -1,10 +1,13 @@
+use aide::axum::ApiRouter;
+use aide::axum::routing::post_with;
 use axum::extract::State;
-use axum::routing::post;
-use axum::{Json, Router};
+use axum::http::StatusCode;
 
+use crate::modules::audit::extractor::ExtractAuditContext;
 use crate::modules::auth::extractor::ExtractAuthInfos;
 use crate::modules::auth::service::AuthService;
-use crate::modules::responses::ApiError;
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::{ApiError, Negotiated};
 use crate::modules::session::dto::SessionTokenDTO;
 use crate::modules::session::service::SessionService;
 use crate::modules::states::AppState;
@@ -13,38 +16,62 @@ use crate::modules::user::dto::UserDto;
 use crate::modules::user::payload::LoginPayload;
 use crate::utils::extractor::ExtractValidated;
 
-pub async fn handle_me(ExtractAuthInfos(auth_session): ExtractAuthInfos) -> Json<UserDto> {
-    Json(auth_session.user)
+pub async fn handle_me(ExtractAuthInfos(auth_session): ExtractAuthInfos) -> Negotiated<UserDto> {
+    Negotiated(auth_session.user)
 }
 
 pub async fn handle_login(
     State(state): State<AppState>,
+    ExtractAuditContext(ctx): ExtractAuditContext,
     ExtractValidated(payload): ExtractValidated<LoginPayload>,
 ) -> ApiResponse<SessionTokenDTO> {
     let auth_svc = AuthService::new(&state.connection);
     auth_svc
-        .login(payload)
+        .login(payload, state.config.session.ttl(), &ctx)
         .await
-        .map(Json)
+        .map(Negotiated)
         .map_err(ApiError::from)
 }
 
 pub async fn handle_logout(
     State(app_state): State<AppState>,
     ExtractAuthInfos(auth_session): ExtractAuthInfos,
+    ExtractAuditContext(ctx): ExtractAuditContext,
 ) -> ApiResponse<SessionTokenDTO> {
+    let session_token = auth_session.session_token.ok_or(ApiError::new(
+        StatusCode::BAD_REQUEST,
+        ErrorCode::BadRequest,
+        "Requests authenticated with a client certificate have no session to revoke",
+    ))?;
+    let ctx = ctx.with_actor(auth_session.user.id);
     let session_service = SessionService::new(&app_state.connection);
     let session_token_dto = session_service
-        .revoke_token(auth_session.session_token.to_string())
+        .revoke_token(session_token.to_string(), &ctx)
         .await?;
-    Ok(Json(SessionTokenDTO {
+    Ok(Negotiated(SessionTokenDTO {
         token: session_token_dto.token,
     }))
 }
 
-pub fn auth_router() -> Router<AppState> {
-    Router::new()
-        .route("/login", post(handle_login))
-        .route("/logout", post(handle_logout))
-        .route("/me", post(handle_me))
+pub fn auth_router() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route(
+            "/login",
+            post_with(handle_login, |op| {
+                op.tag("auth")
+                    .summary("Exchange credentials for a session token")
+            }),
+        )
+        .api_route(
+            "/logout",
+            post_with(handle_logout, |op| {
+                op.tag("auth").summary("Revoke the current session token")
+            }),
+        )
+        .api_route(
+            "/me",
+            post_with(handle_me, |op| {
+                op.tag("auth").summary("Get the authenticated user")
+            }),
+        )
 }
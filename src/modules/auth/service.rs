@@ -1,13 +1,36 @@
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use argon2::password_hash::{PasswordHasher, SaltString, rand_core::OsRng};
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use sea_orm::DatabaseConnection;
-use tracing::error;
+use tracing::{error, info_span, instrument};
 
-use crate::modules::errors::ServiceError;
+use crate::modules::audit::domain::{AuditAction, AuditContext, AuditResult, user_target};
+use crate::modules::audit::service::AuditService;
+use crate::modules::errors::{ErrorCode, ServiceError};
+use crate::modules::metrics::recorder::{record_argon2_duration, record_login};
+use crate::modules::models::entities::user::Model as UserModel;
 use crate::modules::session::dto::SessionTokenDTO;
 use crate::modules::session::service::SessionService;
 use crate::modules::types::ServiceResult;
 use crate::modules::user::payload::LoginPayload;
 use crate::modules::user::service::UserService;
+use crate::utils::logging::email_digest;
+
+/// Verified against when the email is unknown, so that the answer and its timing are the
+/// same as for a wrong password.
+static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| {
+    Argon2::default()
+        .hash_password(b"no such user", &SaltString::generate(&mut OsRng))
+        .map(|hash| hash.to_string())
+        .unwrap_or_default()
+});
+
+fn invalid_credentials() -> ServiceError {
+    ServiceError::unauthorized("Invalid email or password")
+        .with_code(ErrorCode::AuthInvalidCredentials)
+}
 
 pub struct AuthService<'a> {
     db: &'a DatabaseConnection,
@@ -18,32 +41,90 @@ impl<'a> AuthService<'a> {
         Self { db }
     }
 
-    pub async fn login(&self, payload: LoginPayload) -> ServiceResult<SessionTokenDTO> {
-        let user_svc = UserService::new(self.db);
-        let user = user_svc.get_per_email_model(&payload.email).await?;
+    #[instrument(skip_all, fields(user.email_digest = %email_digest(&payload.email)))]
+    pub async fn login(
+        &self,
+        payload: LoginPayload,
+        session_ttl: Duration,
+        ctx: &AuditContext,
+    ) -> ServiceResult<SessionTokenDTO> {
+        let audit_svc = AuditService::new(self.db);
+        let user = match UserService::new(self.db)
+            .get_per_email_model(&payload.email)
+            .await
+        {
+            Ok(user) => Some(user),
+            Err(err) if err.code == ErrorCode::UserNotFound => None,
+            Err(err) => return Err(err),
+        };
+        let target = user_target(user.as_ref().map(|user| user.id), &payload.email);
+
+        let user = match self.verify_credentials(user, payload.password).await {
+            Ok(user) => user,
+            Err(err) => {
+                record_login(false);
+                audit_svc
+                    .record(ctx, AuditAction::Login, Some(target), AuditResult::Failure)
+                    .await;
+                return Err(err);
+            }
+        };
 
-        let password = payload.password;
-        let password_hash = user.password;
-        let email = payload.email;
+        let session_svc = SessionService::new(self.db);
+        let result = session_svc.create(user.id, session_ttl).await;
+        record_login(result.is_ok());
 
+        audit_svc
+            .record(
+                &ctx.clone().with_actor(user.id),
+                AuditAction::Login,
+                Some(target),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    /// `user` is `None` for an unknown email, which then fails like a wrong password.
+    #[instrument(skip_all)]
+    async fn verify_credentials(
+        &self,
+        user: Option<UserModel>,
+        password: String,
+    ) -> ServiceResult<UserModel> {
+        let password_hash = user.as_ref().map(|user| user.password.clone());
+        let user_id = user.as_ref().map(|user| user.id);
+
+        // Entered inside the closure: instrumenting the `JoinHandle` would not cover the hashing.
+        let span = info_span!("argon2.verify");
         tokio::task::spawn_blocking(move || {
+            let _guard = span.enter();
+            let password_hash = password_hash.unwrap_or_else(|| DUMMY_PASSWORD_HASH.clone());
             let hash = PasswordHash::new(&password_hash).map_err(|err| {
                 let err_msg = format!(
-                    "Error, the hash of the user with email: '{}' is not valid",
-                    email
+                    "Error, the hash of the user with id {:?} is not valid",
+                    user_id
                 );
                 error!("{}", err_msg);
                 ServiceError::internal(err_msg).with_details(err.to_string())
             })?;
 
-            Argon2::default()
-                .verify_password(password.as_bytes(), &hash)
-                .map_err(|_| ServiceError::unauthorized("Bad password"))
+            let started_at = Instant::now();
+            let verified = Argon2::default().verify_password(password.as_bytes(), &hash);
+            record_argon2_duration("verify", started_at);
+
+            verified.map_err(|_| invalid_credentials())
         })
         .await
         .map_err(|_| ServiceError::internal("Password verification task failed"))??;
+        // An unknown email fails like a wrong password, after the same amount of work.
+        let user = user.ok_or_else(invalid_credentials)?;
 
-        let session_svc = SessionService::new(self.db);
-        session_svc.create(user.id).await
+        // Checked after the password so the answer does not reveal disabled accounts.
+        if user.disabled_at.is_some() {
+            return Err(ServiceError::forbidden("This account is disabled")
+                .with_code(ErrorCode::AuthUserDisabled));
+        }
+        Ok(user)
     }
 }
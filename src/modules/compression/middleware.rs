@@ -0,0 +1,42 @@
+use axum::extract::Request;
+use axum::http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::ApiError;
+
+/// Request encodings `RequestDecompressionLayer` is built with.
+const SUPPORTED_ENCODINGS: [&str; 4] = ["gzip", "br", "zstd", "identity"];
+
+/// Refuses a body in an encoding the decompression layer cannot read with a problem
+/// document, where the layer itself answers an empty 415.
+pub async fn reject_unsupported_encoding(request: Request, next: Next) -> Response {
+    let unsupported = request
+        .headers()
+        .get_all(CONTENT_ENCODING)
+        .iter()
+        .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+        .map(|encoding| encoding.trim().to_ascii_lowercase())
+        .find(|encoding| !SUPPORTED_ENCODINGS.contains(&encoding.as_str()));
+
+    match unsupported {
+        None => next.run(request).await,
+        Some(encoding) => {
+            let mut response = ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ErrorCode::UnsupportedMediaType,
+                format!(
+                    "`content-encoding: {}` is not supported, use gzip, br or zstd",
+                    encoding
+                ),
+            )
+            .into_response();
+            response
+                .headers_mut()
+                .insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip, br, zstd"));
+            response
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use axum::http::header::HeaderName;
+use axum::http::{HeaderMap, HeaderValue};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Weak ETag of a representation, hashed from its JSON form so the tag is the same
+/// whichever format was negotiated for the body.
+pub fn weak_etag<T: Serialize>(value: &T) -> Option<HeaderValue> {
+    let json = serde_json::to_vec(value).ok()?;
+    let digest = format!("{:x}", Sha256::digest(&json));
+    HeaderValue::from_str(&format!("W/\"{}\"", &digest[..32])).ok()
+}
+
+/// Entity tags listed by an `If-Match` or `If-None-Match` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntityTags {
+    Any,
+    List(Vec<String>),
+}
+
+/// The header is present but not visible ASCII, or lists no tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidEntityTags;
+
+impl EntityTags {
+    /// `None` when the header is absent. A present header which cannot be read is an
+    /// error rather than `None`: a precondition must not pass because it is garbled.
+    pub fn from_headers(
+        headers: &HeaderMap,
+        name: HeaderName,
+    ) -> Result<Option<Self>, InvalidEntityTags> {
+        let mut values = headers.get_all(name).iter().peekable();
+        if values.peek().is_none() {
+            return Ok(None);
+        }
+
+        let mut tags = Vec::new();
+        for value in values {
+            let value = value.to_str().map_err(|_| InvalidEntityTags)?;
+            for tag in value.split(',').map(str::trim) {
+                match tag {
+                    "" => {}
+                    "*" => return Ok(Some(Self::Any)),
+                    tag => tags.push(opaque_tag(tag).to_owned()),
+                }
+            }
+        }
+        if tags.is_empty() {
+            return Err(InvalidEntityTags);
+        }
+        Ok(Some(Self::List(tags)))
+    }
+
+    /// Weak comparison: `W/"a"` and `"a"` match. Every tag this API hands out is weak,
+    /// so `If-Match` compares them the same way.
+    pub fn matches(&self, etag: &HeaderValue) -> bool {
+        match self {
+            Self::Any => true,
+            Self::List(tags) => etag
+                .to_str()
+                .is_ok_and(|etag| tags.iter().any(|tag| tag == opaque_tag(etag))),
+        }
+    }
+}
+
+fn opaque_tag(tag: &str) -> &str {
+    tag.strip_prefix("W/").unwrap_or(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::header::IF_NONE_MATCH;
+    use axum::http::{HeaderMap, HeaderValue};
+
+    use super::{EntityTags, InvalidEntityTags, weak_etag};
+
+    #[test]
+    fn tags_match_weakly_and_any_matches_everything() {
+        let etag = weak_etag(&serde_json::json!({ "id": 1 })).unwrap();
+        let opaque = etag.to_str().unwrap().trim_start_matches("W/").to_owned();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", {}", opaque)).unwrap(),
+        );
+        let tags = EntityTags::from_headers(&headers, IF_NONE_MATCH)
+            .unwrap()
+            .unwrap();
+        assert!(tags.matches(&etag));
+        assert!(!tags.matches(&weak_etag(&serde_json::json!({ "id": 2 })).unwrap()));
+
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("*"));
+        assert_eq!(
+            EntityTags::from_headers(&headers, IF_NONE_MATCH),
+            Ok(Some(EntityTags::Any))
+        );
+
+        headers.insert(
+            IF_NONE_MATCH,
+            HeaderValue::from_bytes(b"\"caf\xe9\"").unwrap(),
+        );
+        assert_eq!(
+            EntityTags::from_headers(&headers, IF_NONE_MATCH),
+            Err(InvalidEntityTags)
+        );
+        assert_eq!(
+            EntityTags::from_headers(&HeaderMap::new(), IF_NONE_MATCH),
+            Ok(None)
+        );
+    }
+}
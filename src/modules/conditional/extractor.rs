@@ -0,0 +1,92 @@
+use aide::OperationInput;
+use aide::generate::GenContext;
+use aide::openapi::{
+    HeaderStyle, Operation, Parameter, ParameterData, ParameterSchemaOrContent, ReferenceOr,
+    SchemaObject,
+};
+use axum::extract::FromRequestParts;
+use axum::http::StatusCode;
+use axum::http::header::IF_MATCH;
+use axum::http::request::Parts;
+use serde::Serialize;
+
+use crate::modules::conditional::domain::{EntityTags, weak_etag};
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::ApiError;
+use crate::utils::extractor::problem_responses;
+
+/// Tags of the `If-Match` header, `None` when the client sent none.
+pub struct ExtractIfMatch(pub Option<EntityTags>);
+
+impl ExtractIfMatch {
+    /// `true` without `If-Match`, or when it lists the ETag `current` is served with.
+    pub fn allows<T: Serialize>(&self, current: &T) -> bool {
+        match &self.0 {
+            None => true,
+            Some(tags) => weak_etag(current).is_some_and(|etag| tags.matches(&etag)),
+        }
+    }
+}
+
+pub fn precondition_failed() -> ApiError {
+    ApiError::new(
+        StatusCode::PRECONDITION_FAILED,
+        ErrorCode::PreconditionFailed,
+        "The resource changed since it was read, `if-match` lists none of its ETags",
+    )
+}
+
+impl<S> FromRequestParts<S> for ExtractIfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        EntityTags::from_headers(&parts.headers, IF_MATCH)
+            .map(ExtractIfMatch)
+            .map_err(|_| {
+                ApiError::new(
+                    StatusCode::PRECONDITION_FAILED,
+                    ErrorCode::PreconditionFailed,
+                    "The `if-match` header lists no readable ETag",
+                )
+            })
+    }
+}
+
+impl OperationInput for ExtractIfMatch {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        let json_schema = ctx.schema.subschema_for::<String>();
+        operation
+            .parameters
+            .push(ReferenceOr::Item(Parameter::Header {
+                parameter_data: ParameterData {
+                    name: IF_MATCH.as_str().to_owned(),
+                    description: Some(
+                        "ETag of the last read, the request fails with 412 if the resource changed since"
+                            .to_owned(),
+                    ),
+                    required: false,
+                    deprecated: None,
+                    format: ParameterSchemaOrContent::Schema(SchemaObject {
+                        json_schema,
+                        example: None,
+                        external_docs: None,
+                    }),
+                    example: None,
+                    examples: Default::default(),
+                    explode: None,
+                    extensions: Default::default(),
+                },
+                style: HeaderStyle::Simple,
+            }));
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, aide::openapi::Response)> {
+        problem_responses(ctx, operation, &[412])
+    }
+}
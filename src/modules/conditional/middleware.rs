@@ -0,0 +1,38 @@
+use axum::extract::Request;
+use axum::http::header::{ETAG, IF_NONE_MATCH, VARY};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::modules::conditional::domain::EntityTags;
+
+/// Answers a `GET` with 304 when `If-None-Match` lists the ETag of the response. The
+/// handler still runs: tags are hashed from the representation it builds.
+pub async fn handle_if_none_match(request: Request, next: Next) -> Response {
+    let tags = match *request.method() {
+        // An unreadable `If-None-Match` only costs the client a full response.
+        Method::GET | Method::HEAD => EntityTags::from_headers(request.headers(), IF_NONE_MATCH)
+            .ok()
+            .flatten(),
+        _ => None,
+    };
+    let response = next.run(request).await;
+
+    let Some(tags) = tags else {
+        return response;
+    };
+    let Some(etag) = response.headers().get(ETAG) else {
+        return response;
+    };
+    if response.status() != StatusCode::OK || !tags.matches(etag) {
+        return response;
+    }
+
+    let mut not_modified = StatusCode::NOT_MODIFIED.into_response();
+    for name in [ETAG, VARY] {
+        for value in response.headers().get_all(&name) {
+            not_modified.headers_mut().append(&name, value.clone());
+        }
+    }
+    not_modified
+}
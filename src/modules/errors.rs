@@ -1,29 +1,244 @@
-
 use axum::http::StatusCode;
-use sea_orm::DbErr;
-use tracing::warn;
+use schemars::JsonSchema;
+use sea_orm::sqlx::error::ErrorKind;
+use sea_orm::sqlx::postgres::PgDatabaseError;
+use sea_orm::{DbErr, RuntimeErr, SqlxError};
+use serde::{Serialize, Serializer};
+use tracing::{error, warn};
+
+/// Stable, machine-readable identifier of an error. Clients match on these
+/// instead of the human-readable message, which may change at any time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[schemars(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    RouteNotFound,
+    MethodNotAllowed,
+    Conflict,
+    InternalError,
+    ServiceUnavailable,
+    UniqueViolation,
+    ReferenceViolation,
+    ValidationFailed,
+    MalformedBody,
+    UnsupportedMediaType,
+    NotAcceptable,
+    PreconditionFailed,
+    PreconditionRequired,
+    VersionConflict,
+    InvalidPathParams,
+    InvalidQuery,
+    PayloadTooLarge,
+    RequestTimeout,
+    AuthMissingToken,
+    AuthMalformedHeader,
+    AuthInvalidScheme,
+    AuthInvalidCredentials,
+    AuthSessionExpired,
+    AuthSessionInvalid,
+    AuthAdminRequired,
+    AuthUserDisabled,
+    AuthCertificateUnmapped,
+    SessionTokenMalformed,
+    SessionNotFound,
+    UserNotFound,
+    UserEmailTaken,
+    RoleAlreadyGranted,
+    RoleNotFound,
+    SetupUnavailable,
+    SetupTokenInvalid,
+    RateLimited,
+    IdempotencyKeyInvalid,
+    IdempotencyKeyInProgress,
+    IdempotencyKeyReused,
+    LogFilterInvalid,
+}
+
+impl ErrorCode {
+    /// Default code used when a `ServiceError` is built from a bare status.
+    pub fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::BAD_REQUEST => Self::BadRequest,
+            StatusCode::UNAUTHORIZED => Self::Unauthorized,
+            StatusCode::FORBIDDEN => Self::Forbidden,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::METHOD_NOT_ALLOWED => Self::MethodNotAllowed,
+            StatusCode::CONFLICT => Self::Conflict,
+            StatusCode::PAYLOAD_TOO_LARGE => Self::PayloadTooLarge,
+            StatusCode::REQUEST_TIMEOUT => Self::RequestTimeout,
+            StatusCode::NOT_ACCEPTABLE => Self::NotAcceptable,
+            StatusCode::PRECONDITION_FAILED => Self::PreconditionFailed,
+            StatusCode::PRECONDITION_REQUIRED => Self::PreconditionRequired,
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => Self::UnsupportedMediaType,
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited,
+            StatusCode::SERVICE_UNAVAILABLE => Self::ServiceUnavailable,
+            _ => Self::InternalError,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "BAD_REQUEST",
+            Self::Unauthorized => "UNAUTHORIZED",
+            Self::Forbidden => "FORBIDDEN",
+            Self::NotFound => "NOT_FOUND",
+            Self::RouteNotFound => "ROUTE_NOT_FOUND",
+            Self::MethodNotAllowed => "METHOD_NOT_ALLOWED",
+            Self::Conflict => "CONFLICT",
+            Self::InternalError => "INTERNAL_ERROR",
+            Self::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+            Self::UniqueViolation => "UNIQUE_VIOLATION",
+            Self::ReferenceViolation => "REFERENCE_VIOLATION",
+            Self::ValidationFailed => "VALIDATION_FAILED",
+            Self::MalformedBody => "MALFORMED_BODY",
+            Self::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            Self::NotAcceptable => "NOT_ACCEPTABLE",
+            Self::PreconditionFailed => "PRECONDITION_FAILED",
+            Self::PreconditionRequired => "PRECONDITION_REQUIRED",
+            Self::VersionConflict => "VERSION_CONFLICT",
+            Self::InvalidPathParams => "INVALID_PATH_PARAMS",
+            Self::InvalidQuery => "INVALID_QUERY",
+            Self::PayloadTooLarge => "PAYLOAD_TOO_LARGE",
+            Self::RequestTimeout => "REQUEST_TIMEOUT",
+            Self::AuthMissingToken => "AUTH_MISSING_TOKEN",
+            Self::AuthMalformedHeader => "AUTH_MALFORMED_HEADER",
+            Self::AuthInvalidScheme => "AUTH_INVALID_SCHEME",
+            Self::AuthInvalidCredentials => "AUTH_INVALID_CREDENTIALS",
+            Self::AuthSessionExpired => "AUTH_SESSION_EXPIRED",
+            Self::AuthSessionInvalid => "AUTH_SESSION_INVALID",
+            Self::AuthAdminRequired => "AUTH_ADMIN_REQUIRED",
+            Self::AuthUserDisabled => "AUTH_USER_DISABLED",
+            Self::AuthCertificateUnmapped => "AUTH_CERTIFICATE_UNMAPPED",
+            Self::SessionTokenMalformed => "SESSION_TOKEN_MALFORMED",
+            Self::SessionNotFound => "SESSION_NOT_FOUND",
+            Self::UserNotFound => "USER_NOT_FOUND",
+            Self::UserEmailTaken => "USER_EMAIL_TAKEN",
+            Self::RoleAlreadyGranted => "ROLE_ALREADY_GRANTED",
+            Self::RoleNotFound => "ROLE_NOT_FOUND",
+            Self::SetupUnavailable => "SETUP_UNAVAILABLE",
+            Self::SetupTokenInvalid => "SETUP_TOKEN_INVALID",
+            Self::RateLimited => "RATE_LIMITED",
+            Self::IdempotencyKeyInvalid => "IDEMPOTENCY_KEY_INVALID",
+            Self::IdempotencyKeyInProgress => "IDEMPOTENCY_KEY_IN_PROGRESS",
+            Self::IdempotencyKeyReused => "IDEMPOTENCY_KEY_REUSED",
+            Self::LogFilterInvalid => "LOG_FILTER_INVALID",
+        }
+    }
+
+    /// Short summary of the problem type, identical for every occurrence (RFC 7807 `title`).
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::BadRequest => "Bad request",
+            Self::Unauthorized => "Unauthorized",
+            Self::Forbidden => "Forbidden",
+            Self::NotFound => "Resource not found",
+            Self::RouteNotFound => "Route not found",
+            Self::MethodNotAllowed => "Method not allowed",
+            Self::Conflict => "Conflict",
+            Self::InternalError => "Internal server error",
+            Self::ServiceUnavailable => "Service temporarily unavailable",
+            Self::UniqueViolation => "Value already exists",
+            Self::ReferenceViolation => "Referenced resource conflict",
+            Self::ValidationFailed => "Validation failed",
+            Self::MalformedBody => "Malformed request body",
+            Self::UnsupportedMediaType => "Unsupported media type",
+            Self::NotAcceptable => "Not acceptable",
+            Self::PreconditionFailed => "Precondition failed",
+            Self::PreconditionRequired => "Precondition required",
+            Self::VersionConflict => "Version conflict",
+            Self::InvalidPathParams => "Invalid path parameters",
+            Self::InvalidQuery => "Invalid query string",
+            Self::PayloadTooLarge => "Request body too large",
+            Self::RequestTimeout => "Request timeout",
+            Self::AuthMissingToken => "Missing authentication token",
+            Self::AuthMalformedHeader => "Malformed authorization header",
+            Self::AuthInvalidScheme => "Invalid authorization scheme",
+            Self::AuthInvalidCredentials => "Invalid credentials",
+            Self::AuthSessionExpired => "Session expired or revoked",
+            Self::AuthSessionInvalid => "Invalid session",
+            Self::AuthAdminRequired => "Admin role required",
+            Self::AuthUserDisabled => "Account disabled",
+            Self::AuthCertificateUnmapped => "Client certificate not mapped to a user",
+            Self::SessionTokenMalformed => "Malformed session token",
+            Self::SessionNotFound => "Session not found",
+            Self::UserNotFound => "User not found",
+            Self::UserEmailTaken => "Email already taken",
+            Self::RoleAlreadyGranted => "Role already granted",
+            Self::RoleNotFound => "Role not found",
+            Self::SetupUnavailable => "Setup no longer available",
+            Self::SetupTokenInvalid => "Invalid setup token",
+            Self::RateLimited => "Too many requests",
+            Self::IdempotencyKeyInvalid => "Invalid idempotency key",
+            Self::IdempotencyKeyInProgress => "Request with this idempotency key in progress",
+            Self::IdempotencyKeyReused => "Idempotency key reused with another request",
+            Self::LogFilterInvalid => "Invalid log filter",
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 #[derive(Debug)]
 pub struct ServiceError {
     pub status: StatusCode,
+    pub code: ErrorCode,
     pub message: String,
     pub details: Option<String>,
+    pub field: Option<String>,
+    /// Representation of the resource as it is now, returned on a version conflict.
+    pub current: Option<serde_json::Value>,
 }
 
 impl ServiceError {
     pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
         Self {
             status,
+            code: ErrorCode::from_status(status),
             message: message.into(),
             details: None,
+            field: None,
+            current: None,
         }
     }
 
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self
+    }
+
     pub fn with_details(mut self, details: impl Into<String>) -> Self {
         self.details = Some(details.into());
         self
     }
 
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    pub fn with_current(mut self, current: &impl Serialize) -> Self {
+        self.current = serde_json::to_value(current).ok();
+        self
+    }
+
     pub fn internal(message: impl Into<String> + tracing::Value) -> Self {
         Self::new(StatusCode::INTERNAL_SERVER_ERROR, message)
     }
@@ -39,6 +254,14 @@ impl ServiceError {
     pub fn bad_request(message: impl Into<String>) -> Self {
         Self::new(StatusCode::BAD_REQUEST, message)
     }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, message)
+    }
 }
 
 impl std::fmt::Display for ServiceError {
@@ -55,9 +278,101 @@ impl From<ServiceError> for (StatusCode, String) {
     }
 }
 
+impl ServiceError {
+    /// Classifies a database error into the matching HTTP error. `context` is only used
+    /// as the message when the error cannot be attributed to the client (plain 500).
+    pub fn from_db(err: DbErr, context: impl Into<String>) -> Self {
+        let details = err.to_string();
+
+        let classified = match &err {
+            DbErr::RecordNotFound(_) | DbErr::RecordNotUpdated => {
+                Some(Self::not_found("The requested record does not exist"))
+            }
+            DbErr::Conn(_) | DbErr::ConnectionAcquire(_) => Some(database_unavailable()),
+            DbErr::Exec(RuntimeErr::SqlxError(sqlx_err))
+            | DbErr::Query(RuntimeErr::SqlxError(sqlx_err)) => classify_sqlx(sqlx_err),
+            _ => None,
+        };
+
+        match classified {
+            Some(classified) => {
+                warn!("DB ERROR ({}): {}", classified.code, details);
+                classified.with_details(details)
+            }
+            None => {
+                error!("DB ERROR: {:#?}", err);
+                Self::internal(context.into()).with_details(details)
+            }
+        }
+    }
+}
+
+fn database_unavailable() -> ServiceError {
+    ServiceError::new(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "The database is temporarily unavailable",
+    )
+}
+
+fn classify_sqlx(err: &SqlxError) -> Option<ServiceError> {
+    match err {
+        SqlxError::PoolTimedOut
+        | SqlxError::PoolClosed
+        | SqlxError::Io(_)
+        | SqlxError::Tls(_)
+        | SqlxError::WorkerCrashed => Some(database_unavailable()),
+        SqlxError::RowNotFound => Some(ServiceError::not_found(
+            "The requested record does not exist",
+        )),
+        SqlxError::Database(db_err) => {
+            let pg_err = db_err.try_downcast_ref::<PgDatabaseError>();
+            let detail = pg_err
+                .and_then(|pg_err| pg_err.detail())
+                .unwrap_or_default();
+            // Postgres only reports the offending column through the detail line,
+            // e.g. `Key (email)=(a@b.c) already exists.`
+            let field = detail
+                .strip_prefix("Key (")
+                .and_then(|rest| rest.split_once(")="))
+                .map(|(field, _)| field.to_owned());
+
+            let classified = match db_err.kind() {
+                ErrorKind::UniqueViolation => ServiceError::conflict(format!(
+                    "A record with this {} already exists",
+                    field.as_deref().unwrap_or("value")
+                ))
+                .with_code(ErrorCode::UniqueViolation),
+                ErrorKind::ForeignKeyViolation if detail.contains("is still referenced") => {
+                    ServiceError::conflict("The record is still referenced by other records")
+                        .with_code(ErrorCode::ReferenceViolation)
+                }
+                ErrorKind::ForeignKeyViolation => ServiceError::new(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "The record references a resource that does not exist",
+                )
+                .with_code(ErrorCode::ReferenceViolation),
+                ErrorKind::NotNullViolation | ErrorKind::CheckViolation => ServiceError::new(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "The record does not satisfy the database constraints",
+                )
+                .with_code(ErrorCode::ValidationFailed),
+                _ => return None,
+            };
+
+            Some(match field {
+                Some(field) => classified.with_field(field),
+                None => classified,
+            })
+        }
+        _ => None,
+    }
+}
+
 impl From<DbErr> for ServiceError {
     fn from(value: DbErr) -> Self {
-        warn!("DB ERROR: {:#?}", value);
-        Self::internal("Internal Error during the processing of your request...")
+        Self::from_db(
+            value,
+            "Internal Error during the processing of your request...",
+        )
     }
 }
@@ -0,0 +1,61 @@
+use std::any::Any;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use tracing::error;
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::ApiError;
+
+/// Turns a panic caught by `CatchPanicLayer` into a 500 `ApiError`. Only builds that
+/// unwind can catch one, a `panic = "abort"` profile still stops the process.
+pub fn handle_panic(panic: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_owned());
+    error!("Request handler panicked: {}", message);
+
+    ApiError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        ErrorCode::InternalError,
+        "An unexpected error occurred",
+    )
+    .with_details(message)
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::{Request, StatusCode};
+    use axum::routing::get;
+    use tower::ServiceExt;
+    use tower_http::catch_panic::CatchPanicLayer;
+
+    use super::handle_panic;
+
+    async fn panicking_handler() -> &'static str {
+        panic!("boom")
+    }
+
+    #[tokio::test]
+    async fn panics_become_problem_documents() {
+        let app = Router::new()
+            .route("/", get(panicking_handler))
+            .layer(CatchPanicLayer::custom(handle_panic));
+
+        let response = app
+            .oneshot(Request::get("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "INTERNAL_ERROR");
+        assert!(body.get("details").is_none());
+    }
+}
@@ -0,0 +1,22 @@
+use axum::http::{Method, StatusCode, Uri};
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::ApiError;
+
+/// Answers the requests no route matches.
+pub async fn handle_not_found(uri: Uri) -> ApiError {
+    ApiError::new(
+        StatusCode::NOT_FOUND,
+        ErrorCode::RouteNotFound,
+        format!("No route matches {}", uri.path()),
+    )
+}
+
+/// Answers a known route called with a method it does not serve, axum still adds `Allow`.
+pub async fn handle_method_not_allowed(method: Method, uri: Uri) -> ApiError {
+    ApiError::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        ErrorCode::MethodNotAllowed,
+        format!("{} is not allowed on {}", method, uri.path()),
+    )
+}
@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Fail,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ComponentHealthDto {
+    pub status: HealthStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct HealthDto {
+    pub status: HealthStatus,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, ComponentHealthDto>,
+}
+
+impl HealthDto {
+    pub fn from_components(components: BTreeMap<String, ComponentHealthDto>) -> Self {
+        let status = if components
+            .values()
+            .all(|component| component.status == HealthStatus::Ok)
+        {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Fail
+        };
+        Self { status, components }
+    }
+}
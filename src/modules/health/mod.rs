@@ -0,0 +1,3 @@
+pub mod dto;
+pub mod route;
+pub mod service;
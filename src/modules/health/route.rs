@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use aide::axum::ApiRouter;
+use aide::axum::routing::get_with;
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+
+use crate::modules::health::dto::{ComponentHealthDto, HealthDto, HealthStatus};
+use crate::modules::health::service::HealthService;
+use crate::modules::states::AppState;
+
+pub fn health_router() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route(
+            "/live",
+            get_with(handle_live, |op| {
+                op.tag("health")
+                    .summary("Liveness probe, answers as long as the process runs")
+            }),
+        )
+        .api_route(
+            "/ready",
+            get_with(handle_ready, |op| {
+                op.tag("health")
+                    .summary("Readiness probe, checks the databases and pending migrations")
+            }),
+        )
+}
+
+async fn handle_live() -> Json<HealthDto> {
+    Json(HealthDto::from_components(BTreeMap::new()))
+}
+
+async fn handle_ready(State(state): State<AppState>) -> (StatusCode, Json<HealthDto>) {
+    let health_svc = HealthService::new(&state.connection);
+    let (database, migrations) = tokio::join!(
+        health_svc.check_database(),
+        health_svc.check_migrations(state.config.database.migration_mode)
+    );
+
+    let shutdown = ComponentHealthDto {
+        status: if state.is_draining() {
+            HealthStatus::Fail
+        } else {
+            HealthStatus::Ok
+        },
+        latency_ms: 0.0,
+        details: state
+            .is_draining()
+            .then(|| "The server is draining".to_owned()),
+    };
+
+    let mut components = BTreeMap::from([
+        ("database".to_owned(), database),
+        ("migrations".to_owned(), migrations),
+        ("shutdown".to_owned(), shutdown),
+    ]);
+    if state.has_read_replica() {
+        let replica = HealthService::new(&state.read_connection)
+            .check_database()
+            .await;
+        components.insert("database_replica".to_owned(), replica);
+    }
+
+    let health = HealthDto::from_components(components);
+
+    let status = match health.status {
+        HealthStatus::Ok => StatusCode::OK,
+        HealthStatus::Fail => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(health))
+}
@@ -0,0 +1,84 @@
+use std::time::Instant;
+
+use sea_orm::DatabaseConnection;
+use tracing::warn;
+
+use crate::modules::health::dto::{ComponentHealthDto, HealthStatus};
+use crate::utils::migrations::{MigrationMode, migration_state};
+
+pub struct HealthService<'a> {
+    db: &'a DatabaseConnection,
+}
+
+impl<'a> HealthService<'a> {
+    pub fn new(db: &'a DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn check_database(&self) -> ComponentHealthDto {
+        let started_at = Instant::now();
+        let result = self.db.ping().await;
+        let latency_ms = started_at.elapsed().as_secs_f64() * 1000.0;
+
+        // The probes are public, the driver error only goes to the logs.
+        match result {
+            Ok(()) => ComponentHealthDto {
+                status: HealthStatus::Ok,
+                latency_ms,
+                details: None,
+            },
+            Err(err) => {
+                warn!("Health check: the database is unreachable: {}", err);
+                ComponentHealthDto {
+                    status: HealthStatus::Fail,
+                    latency_ms,
+                    details: Some("database unreachable".to_owned()),
+                }
+            }
+        }
+    }
+
+    /// Pending migrations only fail readiness with `migration_mode = auto`. With `check` or
+    /// `off` they are applied by an operator, the instance keeps serving meanwhile.
+    pub async fn check_migrations(&self, mode: MigrationMode) -> ComponentHealthDto {
+        let started_at = Instant::now();
+        let state = migration_state(self.db).await;
+        let latency_ms = started_at.elapsed().as_secs_f64() * 1000.0;
+
+        match state {
+            Ok(state) => {
+                let mut details = Vec::new();
+                if !state.pending.is_empty() {
+                    details.push(format!("{} pending migration(s)", state.pending.len()));
+                }
+                if !state.unknown.is_empty() {
+                    details.push(format!(
+                        "{} applied migration(s) unknown to this release",
+                        state.unknown.len()
+                    ));
+                }
+                ComponentHealthDto {
+                    status: if mode == MigrationMode::Auto && !state.pending.is_empty() {
+                        HealthStatus::Fail
+                    } else {
+                        HealthStatus::Ok
+                    },
+                    latency_ms,
+                    details: (!details.is_empty()).then(|| details.join(", ")),
+                }
+            }
+            Err(err) => {
+                warn!("Health check: cannot list the migrations: {}", err);
+                ComponentHealthDto {
+                    status: if mode == MigrationMode::Auto {
+                        HealthStatus::Fail
+                    } else {
+                        HealthStatus::Ok
+                    },
+                    latency_ms,
+                    details: Some("migration status unavailable".to_owned()),
+                }
+            }
+        }
+    }
+}
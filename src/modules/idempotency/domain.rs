@@ -0,0 +1,168 @@
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::request::Parts;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
+use axum::response::Response;
+use sha2::{Digest, Sha256};
+
+use crate::modules::rate_limit::service::X_API_KEY;
+use crate::modules::tls::domain::ClientCertificate;
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+pub const IDEMPOTENT_REPLAYED: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// Value of an `Idempotency-Key` header: 1 to 255 visible ASCII characters.
+pub fn parse_key(value: &HeaderValue) -> Result<&str, String> {
+    let key = value
+        .to_str()
+        .map_err(|_| "The `idempotency-key` header must be visible ASCII".to_owned())?
+        .trim();
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Err(format!(
+            "The `idempotency-key` header must hold 1 to {} characters",
+            MAX_KEY_LENGTH
+        ));
+    }
+    Ok(key)
+}
+
+/// Row key of an idempotency key. Keys are scoped to the credentials sent with them, so
+/// a client cannot replay the response stored for another one by reusing its key.
+pub fn storage_key(parts: &Parts, key: &str) -> String {
+    let mut hasher = Sha256::new();
+    if let Some(certificate) = parts.extensions.get::<ClientCertificate>() {
+        hasher.update(b"certificate:");
+        hasher.update(certificate.fingerprint.as_bytes());
+    } else if let Some(authorization) = parts.headers.get(AUTHORIZATION) {
+        hasher.update(b"authorization:");
+        hasher.update(authorization.as_bytes());
+    } else if let Some(api_key) = parts.headers.get(X_API_KEY) {
+        hasher.update(b"api-key:");
+        hasher.update(api_key.as_bytes());
+    } else {
+        hasher.update(b"anonymous");
+    }
+    hasher.update(b"\n");
+    hasher.update(key.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// What makes two requests sent with the same key the same request.
+pub fn fingerprint(parts: &Parts, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(parts.method.as_str().as_bytes());
+    hasher.update(b"\n");
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or_else(|| parts.uri.path());
+    hasher.update(path.as_bytes());
+    hasher.update(b"\n");
+    if let Some(content_type) = parts.headers.get(CONTENT_TYPE) {
+        hasher.update(content_type.as_bytes());
+    }
+    hasher.update(b"\n");
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Response kept for the TTL of its key and replayed to the retries.
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl StoredResponse {
+    pub fn new(response: &axum::http::response::Parts, body: &[u8]) -> Self {
+        Self {
+            status: response.status.as_u16(),
+            headers: response
+                .headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_owned(), value.to_str().ok()?.to_owned()))
+                })
+                .collect(),
+            body: body.to_vec(),
+        }
+    }
+
+    pub fn into_response(self) -> Response {
+        let mut response = Response::new(Body::from(self.body));
+        *response.status_mut() =
+            StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let headers = response.headers_mut();
+        for (name, value) in self.headers {
+            if let (Ok(name), Ok(value)) =
+                (HeaderName::try_from(name), HeaderValue::try_from(value))
+            {
+                headers.append(name, value);
+            }
+        }
+        headers.insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+/// Outcome of claiming a key for a request.
+#[derive(Debug)]
+pub enum Claim {
+    /// First use of the key, the request runs and its response is stored.
+    Acquired,
+    /// The first request sent with the key has not answered yet.
+    InProgress,
+    /// The key was used for a request with another method, path or body.
+    Mismatch,
+    Completed(StoredResponse),
+}
+
+/// Responses a retry may turn into something else are not stored, the key is released.
+pub fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{HeaderValue, Request};
+
+    use super::{MAX_KEY_LENGTH, fingerprint, parse_key, storage_key};
+
+    #[test]
+    fn keys_are_scoped_and_requests_fingerprinted() {
+        assert!(parse_key(&HeaderValue::from_static("")).is_err());
+        assert!(
+            parse_key(&HeaderValue::from_str(&"k".repeat(MAX_KEY_LENGTH + 1)).unwrap()).is_err()
+        );
+        assert_eq!(
+            parse_key(&HeaderValue::from_static(" retry-1 ")),
+            Ok("retry-1")
+        );
+
+        let (anonymous, _) = Request::post("/users").body(()).unwrap().into_parts();
+        let (authenticated, _) = Request::post("/users")
+            .header("authorization", "Bearer token")
+            .body(())
+            .unwrap()
+            .into_parts();
+        assert_ne!(
+            storage_key(&anonymous, "retry-1"),
+            storage_key(&authenticated, "retry-1")
+        );
+
+        assert_eq!(
+            fingerprint(&anonymous, b"{}"),
+            fingerprint(&authenticated, b"{}")
+        );
+        assert_ne!(
+            fingerprint(&anonymous, b"{}"),
+            fingerprint(&anonymous, b"[]")
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use axum::body::{Body, Bytes};
+use axum::extract::{FromRequest, Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tracing::warn;
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::idempotency::domain::{
+    Claim, IDEMPOTENCY_KEY, StoredResponse, fingerprint, is_retryable, parse_key, storage_key,
+};
+use crate::modules::responses::ApiError;
+use crate::modules::states::AppState;
+
+/// Runs a POST sent with an `Idempotency-Key` once and replays its response to the
+/// retries. A failing store lets the request through, like the rate limiter does.
+pub async fn handle_idempotency_key(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(keys) = state.idempotency.clone() else {
+        return next.run(request).await;
+    };
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(value) = request.headers().get(IDEMPOTENCY_KEY) else {
+        return next.run(request).await;
+    };
+    let key = match parse_key(value) {
+        Ok(key) => key.to_owned(),
+        Err(message) => {
+            return ApiError::new(
+                StatusCode::BAD_REQUEST,
+                ErrorCode::IdempotencyKeyInvalid,
+                message,
+            )
+            .into_response();
+        }
+    };
+
+    let (parts, body) = request.into_parts();
+    let body = match Bytes::from_request(Request::new(body), &()).await {
+        Ok(body) => body,
+        Err(rejection) => return ApiError::from(rejection).into_response(),
+    };
+    let key = storage_key(&parts, &key);
+    let fingerprint = fingerprint(&parts, &body);
+    let request = Request::from_parts(parts, Body::from(body));
+
+    match keys.claim(&key, &fingerprint).await {
+        Ok(Claim::Acquired) => {}
+        Ok(Claim::InProgress) => {
+            let mut response = ApiError::new(
+                StatusCode::CONFLICT,
+                ErrorCode::IdempotencyKeyInProgress,
+                "A request sent with this `idempotency-key` is still running, retry later",
+            )
+            .into_response();
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(1));
+            return response;
+        }
+        Ok(Claim::Mismatch) => {
+            return ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ErrorCode::IdempotencyKeyReused,
+                "This `idempotency-key` was already used for a request with another body",
+            )
+            .into_response();
+        }
+        Ok(Claim::Completed(stored)) => return stored.into_response(),
+        Err(err) => {
+            warn!(
+                "Idempotency store unavailable, request let through: {}",
+                err
+            );
+            return next.run(request).await;
+        }
+    }
+
+    let response = next.run(request).await;
+    if is_retryable(response.status()) {
+        if let Err(err) = keys.release(&key).await {
+            warn!("Failed to release an idempotency key: {}", err);
+        }
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(err) => {
+            warn!(
+                "Failed to buffer a response for its idempotency key: {}",
+                err
+            );
+            if let Err(err) = keys.release(&key).await {
+                warn!("Failed to release an idempotency key: {}", err);
+            }
+            return ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::InternalError,
+                "Failed to read the response",
+            )
+            .into_response();
+        }
+    };
+    if let Err(err) = keys
+        .complete(&key, &StoredResponse::new(&parts, &body))
+        .await
+    {
+        warn!(
+            "Failed to store the response of an idempotency key: {}",
+            err
+        );
+    }
+    Response::from_parts(parts, Body::from(body))
+}
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, debug, warn};
+
+use crate::modules::idempotency::domain::{Claim, StoredResponse};
+use crate::utils::cfg::IdempotencyConfig;
+use crate::utils::telemetry::db_span;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a key stays claimed by a request which has not answered yet. A server dying
+/// mid-request leaves the row behind, the key is usable again once the lease is over.
+const IN_FLIGHT_LEASE: Duration = Duration::from_secs(60);
+
+/// Takes the key unless a live row holds it: a new key, or an expired one whose row the
+/// purge has not deleted yet. `$1` key, `$2` fingerprint, `$3` lease in seconds.
+const CLAIM_SQL: &str = r#"
+INSERT INTO idempotency_key AS idempotency (key, fingerprint, expires_at)
+VALUES ($1, $2, now() + make_interval(secs => $3))
+ON CONFLICT (key) DO UPDATE SET
+    fingerprint = EXCLUDED.fingerprint,
+    status = NULL,
+    headers = NULL,
+    body = NULL,
+    created_at = now(),
+    expires_at = EXCLUDED.expires_at
+WHERE idempotency.expires_at < now()
+RETURNING key
+"#;
+
+const LOOKUP_SQL: &str = r#"
+SELECT fingerprint, status, headers, body FROM idempotency_key
+WHERE key = $1 AND expires_at >= now()
+"#;
+
+/// `$1` key, `$2` status, `$3` headers as JSON, `$4` body, `$5` TTL in seconds.
+const COMPLETE_SQL: &str = r#"
+UPDATE idempotency_key
+SET status = $2, headers = $3, body = $4, expires_at = now() + make_interval(secs => $5)
+WHERE key = $1 AND status IS NULL
+"#;
+
+const RELEASE_SQL: &str = "DELETE FROM idempotency_key WHERE key = $1 AND status IS NULL";
+
+const PURGE_SQL: &str = "DELETE FROM idempotency_key WHERE expires_at < now()";
+
+/// Requests and responses of the `Idempotency-Key` header, shared by every replica.
+pub struct IdempotencyKeys {
+    config: IdempotencyConfig,
+    db: Arc<DatabaseConnection>,
+}
+
+impl IdempotencyKeys {
+    pub fn new(config: IdempotencyConfig, db: Arc<DatabaseConnection>) -> Self {
+        Self { config, db }
+    }
+
+    /// The key is acquired by the first request only, the others learn what became of it.
+    pub async fn claim(&self, key: &str, fingerprint: &str) -> Result<Claim, DbErr> {
+        // The row read after a lost claim may expire in between, the second claim wins then.
+        for _ in 0..2 {
+            let statement = Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                CLAIM_SQL,
+                [
+                    key.into(),
+                    fingerprint.into(),
+                    IN_FLIGHT_LEASE.as_secs_f64().into(),
+                ],
+            );
+            let claimed = self
+                .db
+                .query_one_raw(statement)
+                .instrument(db_span("INSERT", "idempotency_key"))
+                .await?;
+            if claimed.is_some() {
+                return Ok(Claim::Acquired);
+            }
+
+            let statement =
+                Statement::from_sql_and_values(DbBackend::Postgres, LOOKUP_SQL, [key.into()]);
+            let Some(row) = self
+                .db
+                .query_one_raw(statement)
+                .instrument(db_span("SELECT", "idempotency_key"))
+                .await?
+            else {
+                continue;
+            };
+
+            let stored_fingerprint: String = row.try_get("", "fingerprint")?;
+            if stored_fingerprint != fingerprint {
+                return Ok(Claim::Mismatch);
+            }
+            let Some(status) = row.try_get::<Option<i32>>("", "status")? else {
+                return Ok(Claim::InProgress);
+            };
+            let headers: Option<String> = row.try_get("", "headers")?;
+            let body: Option<Vec<u8>> = row.try_get("", "body")?;
+            return Ok(Claim::Completed(StoredResponse {
+                status: status as u16,
+                headers: headers
+                    .and_then(|headers| serde_json::from_str(&headers).ok())
+                    .unwrap_or_default(),
+                body: body.unwrap_or_default(),
+            }));
+        }
+        Err(DbErr::RecordNotFound("idempotency_key".to_owned()))
+    }
+
+    /// Stores the response of an acquired key, replayed until the TTL is over.
+    pub async fn complete(&self, key: &str, response: &StoredResponse) -> Result<(), DbErr> {
+        let headers = serde_json::to_string(&response.headers)
+            .map_err(|err| DbErr::Custom(err.to_string()))?;
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            COMPLETE_SQL,
+            [
+                key.into(),
+                i32::from(response.status).into(),
+                headers.into(),
+                response.body.clone().into(),
+                (self.config.ttl_secs as f64).into(),
+            ],
+        );
+        self.db
+            .execute_raw(statement)
+            .instrument(db_span("UPDATE", "idempotency_key"))
+            .await?;
+        Ok(())
+    }
+
+    /// Gives an acquired key up, the next request sent with it runs again.
+    pub async fn release(&self, key: &str) -> Result<(), DbErr> {
+        let statement =
+            Statement::from_sql_and_values(DbBackend::Postgres, RELEASE_SQL, [key.into()]);
+        self.db
+            .execute_raw(statement)
+            .instrument(db_span("DELETE", "idempotency_key"))
+            .await?;
+        Ok(())
+    }
+
+    /// Background task deleting the expired keys until `shutdown` fires.
+    pub async fn purge_expired(self: Arc<Self>, shutdown: CancellationToken) {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                _ = interval.tick() => match self
+                    .db
+                    .execute_unprepared(PURGE_SQL)
+                    .instrument(db_span("DELETE", "idempotency_key"))
+                    .await
+                {
+                    Ok(result) => debug!("Purged {} idempotency key(s)", result.rows_affected()),
+                    Err(err) => warn!("Failed to purge the idempotency keys: {}", err),
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_LENGTH;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http_body_util::Limited;
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::ApiError;
+use crate::utils::cfg::LimitsConfig;
+
+/// Caps the body size and the handling time of a request, with the overrides of its route.
+/// A declared `Content-Length` over the limit is refused upfront, other bodies fail
+/// in the extractor once the limit is reached.
+pub async fn enforce_limits(
+    State(limits): State<Arc<LimitsConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_default();
+    let max_body_bytes = limits.max_body_bytes(&route);
+    let timeout = limits.request_timeout(&route);
+
+    let content_length = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if content_length.is_some_and(|length| length > max_body_bytes) {
+        return ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::PayloadTooLarge,
+            format!("The request body exceeds {} bytes", max_body_bytes),
+        )
+        .into_response();
+    }
+
+    let request = request.map(|body| Body::new(Limited::new(body, max_body_bytes)));
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => ApiError::new(
+            StatusCode::REQUEST_TIMEOUT,
+            ErrorCode::RequestTimeout,
+            format!(
+                "The request was not handled within {} seconds",
+                timeout.as_secs()
+            ),
+        )
+        .into_response(),
+    }
+}
@@ -0,0 +1,8 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct LogFilterDto {
+    /// Active `EnvFilter` directives.
+    pub filter: String,
+}
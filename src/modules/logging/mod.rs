@@ -0,0 +1,4 @@
+pub mod dto;
+pub mod payload;
+pub mod route;
+pub mod service;
@@ -0,0 +1,10 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use validator::Validate;
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct LogFilterPayload {
+    /// `EnvFilter` directives, e.g. `warn,axum_server_poc=debug,sqlx=off`.
+    #[validate(length(min = 1, max = 1024, message = "The filter cannot be empty"))]
+    pub filter: String,
+}
@@ -0,0 +1,58 @@
+use aide::axum::ApiRouter;
+use aide::axum::routing::get_with;
+use axum::extract::State;
+
+use crate::modules::audit::extractor::ExtractAuditContext;
+use crate::modules::auth::extractor::ExtractAdminInfos;
+use crate::modules::conditional::extractor::{ExtractIfMatch, precondition_failed};
+use crate::modules::logging::dto::LogFilterDto;
+use crate::modules::logging::payload::LogFilterPayload;
+use crate::modules::logging::service::LogFilterService;
+use crate::modules::responses::{ApiError, Negotiated};
+use crate::modules::states::AppState;
+use crate::modules::types::ApiResponse;
+use crate::utils::extractor::ExtractValidated;
+
+pub fn logging_router() -> ApiRouter<AppState> {
+    ApiRouter::new().api_route(
+        "/log-filter",
+        get_with(handle_get_log_filter, |op| {
+            op.tag("admin")
+                .summary("Show the active log filter (admin only)")
+        })
+        .put_with(handle_update_log_filter, |op| {
+            op.tag("admin")
+                .summary("Replace the log filter at runtime (admin only)")
+        }),
+    )
+}
+
+async fn handle_get_log_filter(
+    State(state): State<AppState>,
+    ExtractAdminInfos(_): ExtractAdminInfos,
+) -> ApiResponse<LogFilterDto> {
+    let log_filter_svc = LogFilterService::new(&state.connection, &state.log_filter);
+    log_filter_svc
+        .current()
+        .map(Negotiated)
+        .map_err(ApiError::from)
+}
+
+async fn handle_update_log_filter(
+    State(state): State<AppState>,
+    ExtractAdminInfos(auth_session): ExtractAdminInfos,
+    ExtractAuditContext(ctx): ExtractAuditContext,
+    if_match: ExtractIfMatch,
+    ExtractValidated(payload): ExtractValidated<LogFilterPayload>,
+) -> ApiResponse<LogFilterDto> {
+    let log_filter_svc = LogFilterService::new(&state.connection, &state.log_filter);
+    if !if_match.allows(&log_filter_svc.current()?) {
+        return Err(precondition_failed());
+    }
+    let ctx = ctx.with_actor(auth_session.user.id);
+    log_filter_svc
+        .update(payload.filter, &ctx)
+        .await
+        .map(Negotiated)
+        .map_err(ApiError::from)
+}
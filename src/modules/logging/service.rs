@@ -0,0 +1,61 @@
+use sea_orm::DatabaseConnection;
+use tracing::{info, instrument};
+use tracing_subscriber::EnvFilter;
+
+use crate::modules::audit::domain::{AuditAction, AuditContext, AuditResult};
+use crate::modules::audit::service::AuditService;
+use crate::modules::errors::{ErrorCode, ServiceError};
+use crate::modules::logging::dto::LogFilterDto;
+use crate::modules::types::ServiceResult;
+use crate::utils::logging::LogFilterHandle;
+
+pub struct LogFilterService<'a> {
+    db: &'a DatabaseConnection,
+    handle: &'a LogFilterHandle,
+}
+
+impl<'a> LogFilterService<'a> {
+    pub fn new(db: &'a DatabaseConnection, handle: &'a LogFilterHandle) -> Self {
+        Self { db, handle }
+    }
+
+    pub fn current(&self) -> ServiceResult<LogFilterDto> {
+        self.handle
+            .with_current(|filter| LogFilterDto {
+                filter: filter.to_string(),
+            })
+            .map_err(|err| {
+                ServiceError::internal("The log filter is unavailable")
+                    .with_details(err.to_string())
+            })
+    }
+
+    #[instrument(skip(self, ctx))]
+    pub async fn update(&self, filter: String, ctx: &AuditContext) -> ServiceResult<LogFilterDto> {
+        let result = self.reload(&filter);
+
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::LogFilterChange,
+                Some(filter),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    fn reload(&self, filter: &str) -> ServiceResult<LogFilterDto> {
+        let new_filter = EnvFilter::try_new(filter).map_err(|err| {
+            ServiceError::bad_request(format!("Invalid log filter: {}", err))
+                .with_code(ErrorCode::LogFilterInvalid)
+                .with_field("filter")
+        })?;
+
+        self.handle.reload(new_filter).map_err(|err| {
+            ServiceError::internal("Failed to reload the log filter").with_details(err.to_string())
+        })?;
+        info!("Log filter changed to '{}'", filter);
+        self.current()
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::Instant;
+
+use metrics::{counter, histogram};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+pub const HTTP_RATE_LIMITED_TOTAL: &str = "http_rate_limited_total";
+pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+pub const DB_POOL_MAX_CONNECTIONS: &str = "db_pool_max_connections";
+pub const AUTH_LOGINS_TOTAL: &str = "auth_logins_total";
+pub const SESSIONS_ACTIVE: &str = "sessions_active";
+pub const ARGON2_DURATION_SECONDS: &str = "argon2_duration_seconds";
+
+const HTTP_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+const ARGON2_BUCKETS: &[f64] = &[0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+/// Installs the global recorder. Until this is called every `metrics` macro is a no-op.
+pub fn install_recorder() -> Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_owned()),
+            HTTP_BUCKETS,
+        )?
+        .set_buckets_for_metric(
+            Matcher::Full(ARGON2_DURATION_SECONDS.to_owned()),
+            ARGON2_BUCKETS,
+        )?
+        .install_recorder()
+}
+
+pub fn record_login(success: bool) {
+    let result = if success { "success" } else { "failure" };
+    counter!(AUTH_LOGINS_TOTAL, "result" => result).increment(1);
+}
+
+pub fn record_argon2_duration(operation: &'static str, started_at: Instant) {
+    histogram!(ARGON2_DURATION_SECONDS, "operation" => operation)
+        .record(started_at.elapsed().as_secs_f64());
+}
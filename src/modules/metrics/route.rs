@@ -0,0 +1,38 @@
+use axum::Router;
+use axum::extract::State;
+use axum::routing::get;
+use metrics::gauge;
+use metrics_exporter_prometheus::PrometheusHandle;
+use tracing::warn;
+
+use crate::modules::metrics::recorder::{
+    DB_POOL_CONNECTIONS, DB_POOL_MAX_CONNECTIONS, SESSIONS_ACTIVE,
+};
+use crate::modules::session::service::SessionService;
+use crate::modules::states::AppState;
+
+/// Served on its own listener (`METRICS_ADDR`) so it never reaches the public port.
+pub fn metrics_router(handle: PrometheusHandle) -> Router<AppState> {
+    Router::new().route(
+        "/metrics",
+        get(move |State(state): State<AppState>| async move {
+            refresh_gauges(&state).await;
+            handle.render()
+        }),
+    )
+}
+
+/// Gauges are sampled at scrape time instead of being tracked on every change.
+async fn refresh_gauges(state: &AppState) {
+    let pool = state.connection.get_postgres_connection_pool();
+    let idle = pool.num_idle() as f64;
+    gauge!(DB_POOL_CONNECTIONS, "state" => "idle").set(idle);
+    gauge!(DB_POOL_CONNECTIONS, "state" => "in_use").set(pool.size() as f64 - idle);
+    gauge!(DB_POOL_MAX_CONNECTIONS).set(pool.options().get_max_connections() as f64);
+
+    let session_svc = SessionService::new(&state.connection);
+    match session_svc.count_active().await {
+        Ok(active) => gauge!(SESSIONS_ACTIVE).set(active as f64),
+        Err(err) => warn!("Failed to count active sessions: {}", err),
+    }
+}
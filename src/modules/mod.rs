@@ -1,8 +1,21 @@
+pub mod audit;
 pub mod auth;
+pub mod compression;
+pub mod conditional;
 pub mod errors;
+pub mod fallback;
+pub mod health;
+pub mod idempotency;
+pub mod limits;
+pub mod logging;
+pub mod metrics;
 pub mod models;
+pub mod openapi;
+pub mod rate_limit;
 pub mod responses;
+pub mod security;
 pub mod session;
 pub mod states;
+pub mod tls;
 pub mod types;
 pub mod user;
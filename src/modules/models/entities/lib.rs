@@ -2,6 +2,7 @@
 
 pub mod prelude;
 
+pub mod audit_event;
 pub mod permission;
 pub mod role;
 pub mod session;
@@ -1,5 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
+use chrono::Utc;
+use sea_orm::ActiveValue;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +13,8 @@ pub struct Model {
     pub id: String,
     pub description: Option<String>,
     pub role_id: i32,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
     #[sea_orm(
         belongs_to,
         from = "role_id",
@@ -21,4 +25,18 @@ pub struct Model {
     pub role: HasOne<super::role::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Same timestamps as for users, permissions are not versioned.
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now().fixed_offset();
+        if insert {
+            self.created_at = ActiveValue::Set(now);
+        }
+        self.updated_at = ActiveValue::Set(now);
+        Ok(self)
+    }
+}
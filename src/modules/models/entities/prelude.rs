@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
+pub use super::audit_event::Entity as AuditEvent;
 pub use super::permission::Entity as Permission;
 pub use super::role::Entity as Role;
 pub use super::session::Entity as Session;
@@ -1,5 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
+use chrono::Utc;
+use sea_orm::ActiveValue;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -12,6 +14,9 @@ pub struct Model {
     pub name: String,
     pub description: Option<String>,
     pub user_id: i32,
+    pub version: i32,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
     #[sea_orm(has_many)]
     pub permissions: HasMany<super::permission::Entity>,
     #[sea_orm(
@@ -24,4 +29,20 @@ pub struct Model {
     pub user: HasOne<super::user::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Same timestamps and `version` bump as for users.
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now().fixed_offset();
+        if insert {
+            self.created_at = ActiveValue::Set(now);
+        } else if let ActiveValue::Unchanged(version) = self.version {
+            self.version = ActiveValue::Set(version + 1);
+        }
+        self.updated_at = ActiveValue::Set(now);
+        Ok(self)
+    }
+}
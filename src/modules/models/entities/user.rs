@@ -1,5 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
+use chrono::Utc;
+use sea_orm::ActiveValue;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -13,10 +15,31 @@ pub struct Model {
     #[sea_orm(unique)]
     pub email: String,
     pub password: String,
+    pub disabled_at: Option<DateTimeWithTimeZone>,
+    pub version: i32,
+    pub created_at: DateTimeWithTimeZone,
+    pub updated_at: DateTimeWithTimeZone,
     #[sea_orm(has_many)]
     pub roles: HasMany<super::role::Entity>,
     #[sea_orm(has_many)]
     pub sessions: HasMany<super::session::Entity>,
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait::async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Stamps `created_at` and `updated_at`. Every update bumps `version`, so a write
+    /// based on a stale read can be detected.
+    async fn before_save<C>(mut self, _db: &C, insert: bool) -> Result<Self, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let now = Utc::now().fixed_offset();
+        if insert {
+            self.created_at = ActiveValue::Set(now);
+        } else if let ActiveValue::Unchanged(version) = self.version {
+            self.version = ActiveValue::Set(version + 1);
+        }
+        self.updated_at = ActiveValue::Set(now);
+        Ok(self)
+    }
+}
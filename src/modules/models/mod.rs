@@ -1,4 +1,5 @@
 pub mod entities {
+    pub mod audit_event;
     pub mod permission;
     pub mod post;
     pub mod prelude;
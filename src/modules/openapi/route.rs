@@ -0,0 +1,99 @@
+use aide::axum::ApiRouter;
+use aide::openapi::{OpenApi, SecurityScheme};
+use aide::scalar::Scalar;
+use aide::transform::TransformOpenApi;
+use axum::Router;
+use axum::http::header::CONTENT_TYPE;
+use axum::middleware;
+use axum::routing::{MethodRouter, get};
+
+use crate::modules::audit::route::audit_router;
+use crate::modules::auth::extractor::BEARER_AUTH;
+use crate::modules::auth::route::auth_router;
+use crate::modules::conditional::middleware::handle_if_none_match;
+use crate::modules::health::route::health_router;
+use crate::modules::logging::route::logging_router;
+use crate::modules::security::headers::set_docs_csp;
+use crate::modules::states::AppState;
+use crate::modules::user::route::user_router;
+use crate::utils::negotiation::negotiate_format;
+
+pub const OPENAPI_PATH: &str = "/openapi.json";
+
+/// Every documented route of the API. Undocumented routes are added by `build_app`.
+/// Probes stay out of content negotiation and always answer JSON.
+pub fn api_router() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .nest("/auth", auth_router())
+        .nest("/users", user_router())
+        .nest("/audit", audit_router())
+        .nest("/admin", logging_router())
+        .layer(middleware::from_fn(negotiate_format))
+        .layer(middleware::from_fn(handle_if_none_match))
+        .nest("/health", health_router())
+}
+
+pub fn api_docs(api: TransformOpenApi<'_>) -> TransformOpenApi<'_> {
+    api.title("axum-server-poc")
+        .version(env!("CARGO_PKG_VERSION"))
+        .security_scheme(
+            BEARER_AUTH,
+            SecurityScheme::Http {
+                scheme: "bearer".to_owned(),
+                bearer_format: Some("uuid".to_owned()),
+                description: Some("Session token returned by `POST /auth/login`".to_owned()),
+                extensions: Default::default(),
+            },
+        )
+}
+
+pub fn openapi() -> OpenApi {
+    let mut api = OpenApi::default();
+    let _ = api_router().finish_api_with(&mut api, api_docs);
+    api
+}
+
+/// Serves the generated document and a Scalar UI at `/docs`.
+pub fn openapi_router(api: &OpenApi) -> Router<AppState> {
+    let spec = serde_json::to_string(api).expect("The OpenAPI document is serializable");
+
+    Router::new()
+        .route(
+            OPENAPI_PATH,
+            get(move || async move { ([(CONTENT_TYPE, "application/json")], spec) }),
+        )
+        .route(
+            "/docs",
+            MethodRouter::from(
+                Scalar::new(OPENAPI_PATH)
+                    .with_title("axum-server-poc API")
+                    .axum_route(),
+            )
+            .layer(middleware::map_response(set_docs_csp)),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::openapi;
+
+    const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/docs/openapi.json");
+
+    /// Regenerate the snapshot with `UPDATE_OPENAPI=1 cargo test openapi`.
+    #[test]
+    fn openapi_spec_matches_committed_snapshot() {
+        let generated = serde_json::to_string_pretty(&openapi()).unwrap() + "\n";
+
+        if std::env::var_os("UPDATE_OPENAPI").is_some() {
+            std::fs::write(SNAPSHOT, &generated).unwrap();
+            return;
+        }
+
+        let committed = std::fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        assert!(
+            generated == committed,
+            "The OpenAPI spec drifted from docs/openapi.json, \
+             rerun with UPDATE_OPENAPI=1 and commit the result"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+/// Token bucket: holds at most `burst` requests and refills at `requests_per_minute`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct RatePolicy {
+    #[validate(range(min = 1, message = "requests_per_minute must be at least 1"))]
+    pub requests_per_minute: u32,
+    #[validate(range(min = 1, message = "burst must be at least 1"))]
+    pub burst: u32,
+}
+
+impl RatePolicy {
+    pub fn capacity(&self) -> f64 {
+        f64::from(self.burst)
+    }
+
+    pub fn refill_per_sec(&self) -> f64 {
+        f64::from(self.requests_per_minute) / 60.0
+    }
+
+    /// Time for an empty bucket to be full again, after which it can be forgotten.
+    pub fn refill_time(&self) -> Duration {
+        Duration::from_secs_f64(self.capacity() / self.refill_per_sec())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitStoreKind {
+    /// Buckets live in the process, each replica limits on its own.
+    #[default]
+    Memory,
+    /// Buckets are shared by every replica through the `rate_limit_bucket` table.
+    Database,
+}
+
+impl FromStr for RateLimitStoreKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "memory" => Ok(Self::Memory),
+            "database" => Ok(Self::Database),
+            other => Err(format!(
+                "Unknown rate limit store '{}', expected memory or database",
+                other
+            )),
+        }
+    }
+}
+
+/// Who a request is counted against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitKey {
+    User(i32),
+    /// SHA-256 of the key, the key itself never leaves the request.
+    ApiKey(String),
+    Ip(String),
+}
+
+impl RateLimitKey {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::User(_) => "user",
+            Self::ApiKey(_) => "api_key",
+            Self::Ip(_) => "ip",
+        }
+    }
+}
+
+impl fmt::Display for RateLimitKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User(id) => write!(f, "user:{}", id),
+            Self::ApiKey(digest) => write!(f, "api_key:{}", digest),
+            Self::Ip(ip) => write!(f, "ip:{}", ip),
+        }
+    }
+}
+
+/// Outcome of taking a token, with what the `RateLimit-*` headers report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Until the bucket is full again.
+    pub reset_after: Duration,
+    /// Until the next token, zero when the request was allowed.
+    pub retry_after: Duration,
+}
+
+impl Decision {
+    /// `tokens` is what is left in the bucket once the request has been counted.
+    pub fn new(policy: RatePolicy, tokens: f64, allowed: bool) -> Self {
+        let rate = policy.refill_per_sec();
+        let tokens = tokens.clamp(0.0, policy.capacity());
+        Self {
+            allowed,
+            limit: policy.burst,
+            remaining: tokens.floor() as u32,
+            reset_after: Duration::from_secs_f64((policy.capacity() - tokens) / rate),
+            retry_after: if allowed {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64((1.0 - tokens) / rate)
+            },
+        }
+    }
+}
+
+/// In-memory bucket, the database store runs the same arithmetic in SQL.
+#[derive(Debug, Clone, Copy)]
+pub struct Bucket {
+    pub tokens: f64,
+    /// Seconds on the caller's clock.
+    pub updated_at: f64,
+}
+
+impl Bucket {
+    pub fn full(policy: RatePolicy, now: f64) -> Self {
+        Self {
+            tokens: policy.capacity(),
+            updated_at: now,
+        }
+    }
+
+    pub fn take(&mut self, policy: RatePolicy, now: f64) -> Decision {
+        let elapsed = (now - self.updated_at).max(0.0);
+        self.tokens = (self.tokens + elapsed * policy.refill_per_sec()).min(policy.capacity());
+        self.updated_at = now;
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+        Decision::new(policy, self.tokens, allowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Bucket, RatePolicy};
+
+    const POLICY: RatePolicy = RatePolicy {
+        requests_per_minute: 60,
+        burst: 3,
+    };
+
+    #[test]
+    fn bucket_allows_the_burst_then_refills_over_time() {
+        let mut bucket = Bucket::full(POLICY, 0.0);
+        for remaining in [2, 1, 0] {
+            let decision = bucket.take(POLICY, 0.0);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+        }
+
+        let denied = bucket.take(POLICY, 0.5);
+        assert!(!denied.allowed);
+        assert_eq!(denied.limit, 3);
+        assert_eq!(denied.retry_after, Duration::from_millis(500));
+
+        let allowed = bucket.take(POLICY, 1.0);
+        assert!(allowed.allowed);
+        assert_eq!(allowed.remaining, 0);
+        assert_eq!(allowed.reset_after, Duration::from_secs(3));
+
+        let refilled = bucket.take(POLICY, 60.0);
+        assert!(refilled.allowed);
+        assert_eq!(refilled.remaining, 2);
+    }
+}
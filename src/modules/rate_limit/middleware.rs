@@ -0,0 +1,91 @@
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use metrics::counter;
+use tracing::warn;
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::metrics::recorder::HTTP_RATE_LIMITED_TOTAL;
+use crate::modules::rate_limit::domain::Decision;
+use crate::modules::responses::ApiError;
+use crate::modules::states::AppState;
+
+pub const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+/// Counts the request against the bucket of its API key or address, then against the one
+/// of its user when it carries valid credentials, and answers 429 once a bucket is empty.
+/// A failing store lets the request through: rate limiting must not take the API down.
+pub async fn rate_limit(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let Some(limiter) = state.rate_limiter.clone() else {
+        return next.run(request).await;
+    };
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".to_owned());
+    let Some((scope, policy)) = limiter.policy_for(&route) else {
+        return next.run(request).await;
+    };
+
+    let (mut parts, body) = request.into_parts();
+    let mut key = limiter.anonymous_key(&parts);
+    let mut decision = match limiter.take(scope, &key, policy).await {
+        Ok(decision) => decision,
+        Err(err) => {
+            warn!("Rate limit store unavailable, request let through: {}", err);
+            return next.run(Request::from_parts(parts, body)).await;
+        }
+    };
+
+    // Credentials are only checked for admitted requests, a flood of forged tokens is
+    // stopped by the bucket of its address before reaching the session table.
+    if decision.allowed
+        && let Some(user_key) = limiter.user_key(&mut parts, &state).await
+    {
+        match limiter.take(scope, &user_key, policy).await {
+            Ok(user_decision) => {
+                key = user_key;
+                decision = user_decision;
+            }
+            Err(err) => warn!("Rate limit store unavailable for {}: {}", user_key, err),
+        }
+    }
+    let request = Request::from_parts(parts, body);
+
+    if !decision.allowed {
+        counter!(HTTP_RATE_LIMITED_TOTAL, "route" => route, "key" => key.kind()).increment(1);
+        let retry_after = ceil_secs(decision.retry_after.as_secs_f64()).max(1);
+        let mut response = ApiError::new(
+            StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::RateLimited,
+            format!("Too many requests, retry in {} second(s)", retry_after),
+        )
+        .into_response();
+        let headers = response.headers_mut();
+        insert_rate_limit_headers(headers, &decision);
+        headers.insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    insert_rate_limit_headers(response.headers_mut(), &decision);
+    response
+}
+
+fn insert_rate_limit_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert(RATELIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(
+        RATELIMIT_RESET,
+        HeaderValue::from(ceil_secs(decision.reset_after.as_secs_f64())),
+    );
+}
+
+fn ceil_secs(secs: f64) -> u64 {
+    secs.ceil() as u64
+}
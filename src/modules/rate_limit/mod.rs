@@ -0,0 +1,4 @@
+pub mod domain;
+pub mod middleware;
+pub mod service;
+pub mod store;
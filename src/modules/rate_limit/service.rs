@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use sea_orm::{DatabaseConnection, DbErr};
+use sha2::{Digest, Sha256};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+use crate::modules::auth::extractor::ExtractAuthInfos;
+use crate::modules::rate_limit::domain::{Decision, RateLimitKey, RateLimitStoreKind, RatePolicy};
+use crate::modules::rate_limit::store::{DatabaseStore, MemoryStore, RateLimitStore};
+use crate::modules::states::AppState;
+use crate::modules::tls::domain::ClientCertificate;
+use crate::utils::cfg::RateLimitConfig;
+use crate::utils::client_ip::client_ip;
+
+pub const X_API_KEY: &str = "x-api-key";
+
+/// Bucket scope shared by every route without a policy of its own.
+const DEFAULT_SCOPE: &str = "*";
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig, store: Arc<dyn RateLimitStore>) -> Self {
+        Self { config, store }
+    }
+
+    pub fn from_config(config: &RateLimitConfig, db: Arc<DatabaseConnection>) -> Self {
+        let store: Arc<dyn RateLimitStore> = match config.store {
+            RateLimitStoreKind::Memory => Arc::new(MemoryStore::default()),
+            RateLimitStoreKind::Database => Arc::new(DatabaseStore::new(db)),
+        };
+        Self::new(config.clone(), store)
+    }
+
+    /// Scope and policy of a route template, `None` when the route is exempt.
+    /// Routes with a policy of their own get their own buckets, the others share one.
+    pub fn policy_for<'r>(&'r self, route: &'r str) -> Option<(&'r str, RatePolicy)> {
+        if self
+            .config
+            .exempt_routes
+            .iter()
+            .any(|exempt| exempt == route)
+        {
+            return None;
+        }
+        Some(
+            self.config
+                .route_policy(route)
+                .unwrap_or_else(|| (DEFAULT_SCOPE, self.config.default_policy())),
+        )
+    }
+
+    /// A known API key, else the client address. Taken before any credentials are checked,
+    /// so that forged ones cannot reach the database unthrottled.
+    pub fn anonymous_key(&self, parts: &Parts) -> RateLimitKey {
+        if let Some(api_key) = parts.headers.get(X_API_KEY) {
+            let digest = format!("{:x}", Sha256::digest(api_key.as_bytes()));
+            // Unknown keys are free to forge, they must not get a bucket of their own.
+            if self
+                .config
+                .api_keys
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(&digest))
+            {
+                return RateLimitKey::ApiKey(digest);
+            }
+        }
+
+        RateLimitKey::Ip(client_ip(parts, &self.config).unwrap_or_else(|| "unknown".to_owned()))
+    }
+
+    /// The authenticated user, once the request was admitted under its anonymous key.
+    /// A resolved session is stored in the extensions for `ExtractAuthInfos` to reuse.
+    pub async fn user_key(&self, parts: &mut Parts, state: &AppState) -> Option<RateLimitKey> {
+        if !parts.headers.contains_key(AUTHORIZATION)
+            && parts.extensions.get::<ClientCertificate>().is_none()
+        {
+            return None;
+        }
+        let ExtractAuthInfos(auth_session) = ExtractAuthInfos::from_request_parts(parts, state)
+            .await
+            .ok()?;
+        let user_id = auth_session.user.id;
+        parts.extensions.insert(auth_session);
+        Some(RateLimitKey::User(user_id))
+    }
+
+    pub async fn take(
+        &self,
+        scope: &str,
+        key: &RateLimitKey,
+        policy: RatePolicy,
+    ) -> Result<Decision, DbErr> {
+        self.store.take(&format!("{}|{}", scope, key), policy).await
+    }
+
+    /// Background task forgetting the full buckets until `shutdown` fires.
+    pub async fn purge_expired(self: Arc<Self>, shutdown: CancellationToken) {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                _ = interval.tick() => match self.store.purge().await {
+                    Ok(purged) => debug!("Purged {} rate limit bucket(s)", purged),
+                    Err(err) => warn!("Failed to purge the rate limit buckets: {}", err),
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Statement};
+use tracing::Instrument;
+
+use crate::modules::rate_limit::domain::{Bucket, Decision, RatePolicy};
+use crate::utils::telemetry::db_span;
+
+/// Where the buckets are kept. `take` must be atomic per key.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    async fn take(&self, key: &str, policy: RatePolicy) -> Result<Decision, DbErr>;
+
+    /// Forgets the buckets which are full again, they behave like missing ones.
+    async fn purge(&self) -> Result<u64, DbErr>;
+}
+
+struct MemoryBucket {
+    bucket: Bucket,
+    full_at: f64,
+}
+
+pub struct MemoryStore {
+    started_at: Instant,
+    buckets: Mutex<HashMap<String, MemoryBucket>>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self {
+            started_at: Instant::now(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl MemoryStore {
+    fn now(&self) -> f64 {
+        self.started_at.elapsed().as_secs_f64()
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn take(&self, key: &str, policy: RatePolicy) -> Result<Decision, DbErr> {
+        let now = self.now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let entry = buckets
+            .entry(key.to_owned())
+            .or_insert_with(|| MemoryBucket {
+                bucket: Bucket::full(policy, now),
+                full_at: now,
+            });
+        let decision = entry.bucket.take(policy, now);
+        entry.full_at = now + decision.reset_after.as_secs_f64();
+        Ok(decision)
+    }
+
+    async fn purge(&self) -> Result<u64, DbErr> {
+        let now = self.now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let before = buckets.len();
+        buckets.retain(|_, entry| entry.full_at > now);
+        Ok((before - buckets.len()) as u64)
+    }
+}
+
+/// Refills and takes a token in a single upsert, the row lock serializes concurrent
+/// requests of every replica. `$1` key, `$2` capacity, `$3` tokens per second.
+const TAKE_SQL: &str = r#"
+INSERT INTO rate_limit_bucket AS bucket (key, tokens, allowed, updated_at, expires_at)
+VALUES ($1, $2 - 1, TRUE, now(), now() + make_interval(secs => $2 / $3))
+ON CONFLICT (key) DO UPDATE SET
+    allowed = LEAST($2, bucket.tokens + EXTRACT(EPOCH FROM now() - bucket.updated_at) * $3) >= 1,
+    tokens = LEAST($2, bucket.tokens + EXTRACT(EPOCH FROM now() - bucket.updated_at) * $3)
+        - CASE
+            WHEN LEAST($2, bucket.tokens + EXTRACT(EPOCH FROM now() - bucket.updated_at) * $3) >= 1
+            THEN 1
+            ELSE 0
+        END,
+    updated_at = now(),
+    expires_at = now() + make_interval(secs => $2 / $3)
+RETURNING tokens, allowed
+"#;
+
+const PURGE_SQL: &str = "DELETE FROM rate_limit_bucket WHERE expires_at < now()";
+
+/// Shared by every replica of the server.
+pub struct DatabaseStore {
+    db: Arc<DatabaseConnection>,
+}
+
+impl DatabaseStore {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for DatabaseStore {
+    async fn take(&self, key: &str, policy: RatePolicy) -> Result<Decision, DbErr> {
+        let statement = Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            TAKE_SQL,
+            [
+                key.into(),
+                policy.capacity().into(),
+                policy.refill_per_sec().into(),
+            ],
+        );
+        let row = self
+            .db
+            .query_one_raw(statement)
+            .instrument(db_span("INSERT", "rate_limit_bucket"))
+            .await?
+            .ok_or_else(|| DbErr::RecordNotFound("rate_limit_bucket".to_owned()))?;
+
+        let tokens: f64 = row.try_get("", "tokens")?;
+        let allowed: bool = row.try_get("", "allowed")?;
+        Ok(Decision::new(policy, tokens, allowed))
+    }
+
+    async fn purge(&self) -> Result<u64, DbErr> {
+        let result = self
+            .db
+            .execute_unprepared(PURGE_SQL)
+            .instrument(db_span("DELETE", "rate_limit_bucket"))
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
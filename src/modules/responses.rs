@@ -1,53 +1,282 @@
+use std::collections::BTreeMap;
+
+use aide::OperationOutput;
+use aide::generate::GenContext;
+use aide::openapi::{MediaType, Operation, Response as OpenApiResponse, SchemaObject};
 use axum::{
     Json,
-    http::StatusCode,
+    extract::{Request, State},
+    http::{
+        HeaderValue, StatusCode,
+        header::{ACCEPT, CONTENT_TYPE, ETAG, VARY},
+    },
+    middleware::Next,
     response::{IntoResponse, Response},
 };
+use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::Serialize;
-use validator::ValidationErrors;
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::modules::conditional::domain::weak_etag;
+use crate::modules::errors::{ErrorCode, ServiceError};
+use crate::modules::states::AppState;
+use crate::utils::negotiation::{add_binary_media_types, current_response_format};
+use crate::utils::request_id::current_request_id;
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+tokio::task_local! {
+    static EXPOSE_ERROR_DETAILS: bool;
+}
+
+/// Raw error details (database messages, hashing errors...) only reach clients when the
+/// configuration of the app serving the request enables `debug`.
+pub async fn scope_error_details(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    EXPOSE_ERROR_DETAILS
+        .scope(state.config.debug, next.run(request))
+        .await
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FieldError {
+    pub code: String,
+    pub message: String,
+}
 
-use crate::modules::errors::ServiceError;
+impl From<&ValidationError> for FieldError {
+    fn from(error: &ValidationError) -> Self {
+        Self {
+            code: error.code.to_string(),
+            message: error
+                .message
+                .as_ref()
+                .map(|message| message.to_string())
+                .unwrap_or_else(|| error.code.to_string()),
+        }
+    }
+}
 
-#[derive(Serialize)]
+/// RFC 7807 problem document, extended with a stable `code` and per-field validation errors.
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ApiError {
+    #[serde(rename = "type")]
+    problem_type: String,
+    title: &'static str,
     status: u16,
-    pub error: String,
+    pub code: ErrorCode,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<BTreeMap<String, Vec<FieldError>>>,
+    /// Current representation of the resource, when a write was based on a stale version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<serde_json::Value>,
+    /// Echo of the `x-request-id` response header, to correlate a report with traces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
 
 impl ApiError {
-    pub fn new(status: StatusCode, error: String, details: Option<String>) -> Self {
+    pub fn new(status: StatusCode, code: ErrorCode, detail: impl Into<String>) -> Self {
         Self {
+            problem_type: format!(
+                "urn:problem-type:{}",
+                code.as_str().to_lowercase().replace('_', "-")
+            ),
+            title: code.title(),
             status: status.into(),
-            details,
-            error,
+            code,
+            detail: detail.into(),
+            details: None,
+            field: None,
+            errors: None,
+            current: None,
+            request_id: None,
         }
     }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or_default()
+    }
 }
 
 impl From<ServiceError> for ApiError {
     fn from(err: ServiceError) -> Self {
-        Self {
-            status: err.status.into(),
-            error: err.message,
-            details: err.details,
-        }
+        let mut api_error = Self::new(err.status, err.code, err.message);
+        api_error.details = err.details;
+        api_error.field = err.field;
+        api_error.current = err.current;
+        api_error
     }
 }
 
 impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let status = StatusCode::from_u16(self.status).unwrap_or_default();
-        (status, Json(self)).into_response()
+    fn into_response(mut self) -> Response {
+        if !EXPOSE_ERROR_DETAILS
+            .try_with(|expose| *expose)
+            .unwrap_or(false)
+        {
+            self.details = None;
+        }
+        if self.request_id.is_none() {
+            self.request_id = current_request_id();
+        }
+        (self.status(), [(CONTENT_TYPE, PROBLEM_JSON)], Json(self)).into_response()
+    }
+}
+
+impl OperationOutput for ApiError {
+    type Inner = Self;
+
+    fn operation_response(
+        ctx: &mut GenContext,
+        _operation: &mut Operation,
+    ) -> Option<OpenApiResponse> {
+        Some(OpenApiResponse {
+            description: "Problem details (RFC 7807)".to_owned(),
+            content: IndexMap::from_iter([(
+                PROBLEM_JSON.to_owned(),
+                MediaType {
+                    schema: Some(SchemaObject {
+                        json_schema: ctx.schema.subschema_for::<Self>(),
+                        example: None,
+                        external_docs: None,
+                    }),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        })
+    }
+
+    fn inferred_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, OpenApiResponse)> {
+        Self::operation_response(ctx, operation)
+            .map(|response| vec![(None, response)])
+            .unwrap_or_default()
     }
 }
 
 impl From<ValidationErrors> for ApiError {
     fn from(error: ValidationErrors) -> Self {
-        Self {
-            details: Some(error.to_string()),
-            error: "The request body is malformated".to_string(),
-            status: StatusCode::BAD_REQUEST.as_u16(),
+        let mut errors = BTreeMap::new();
+        collect_field_errors(None, &error, &mut errors);
+
+        let mut api_error = Self::new(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::ValidationFailed,
+            "The request is invalid",
+        );
+        api_error.errors = Some(errors);
+        api_error
+    }
+}
+
+/// Flattens nested validation errors into `field`, `parent.field` and `list[0].field` keys.
+pub fn collect_field_errors(
+    prefix: Option<&str>,
+    errors: &ValidationErrors,
+    out: &mut BTreeMap<String, Vec<FieldError>>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, field),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => out
+                .entry(path)
+                .or_default()
+                .extend(field_errors.iter().map(FieldError::from)),
+            ValidationErrorsKind::Struct(nested) => collect_field_errors(Some(&path), nested, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_field_errors(Some(&format!("{}[{}]", path, index)), nested, out);
+                }
+            }
+        }
+    }
+}
+
+/// `Json` written in the format negotiated by `negotiate_format`, JSON when the route
+/// has no such layer. Carries the weak ETag of the value.
+pub struct Negotiated<T>(pub T);
+
+impl<T: Serialize> IntoResponse for Negotiated<T> {
+    fn into_response(self) -> Response {
+        let format = current_response_format();
+        match format.encode(&self.0) {
+            Ok(body) => {
+                let mut response = (
+                    [
+                        (CONTENT_TYPE, HeaderValue::from_static(format.mime())),
+                        (VARY, HeaderValue::from_static(ACCEPT.as_str())),
+                    ],
+                    body,
+                )
+                    .into_response();
+                if let Some(etag) = weak_etag(&self.0) {
+                    response.headers_mut().insert(ETAG, etag);
+                }
+                response
+            }
+            Err(err) => ApiError::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorCode::InternalError,
+                "Failed to serialize the response",
+            )
+            .with_details(err)
+            .into_response(),
         }
     }
 }
+
+impl<T: JsonSchema> OperationOutput for Negotiated<T> {
+    type Inner = T;
+
+    fn operation_response(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Option<OpenApiResponse> {
+        let mut response = Json::<T>::operation_response(ctx, operation)?;
+        add_binary_media_types(&mut response.content);
+        Some(response)
+    }
+
+    fn inferred_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, OpenApiResponse)> {
+        let mut responses = Vec::from_iter(
+            Self::operation_response(ctx, operation).map(|response| (Some(200), response)),
+        );
+        responses.extend(
+            ApiError::operation_response(ctx, operation).map(|response| (Some(406), response)),
+        );
+        responses
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub page: u64,
+    pub per_page: u64,
+    pub total: u64,
+}
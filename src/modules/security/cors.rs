@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, RETRY_AFTER};
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::modules::idempotency::domain::{IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED};
+use crate::modules::rate_limit::middleware::{
+    RATELIMIT_LIMIT, RATELIMIT_REMAINING, RATELIMIT_RESET,
+};
+use crate::modules::rate_limit::service::X_API_KEY;
+use crate::modules::user::domain::X_SETUP_TOKEN;
+use crate::utils::cfg::CorsConfig;
+use crate::utils::request_id::X_REQUEST_ID;
+
+/// `None` when no origin is allowed: browsers then only reach the API from its own origin.
+/// Origins are validated with the configuration.
+pub fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+    if config.allowed_origins.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ])
+            .allow_headers([
+                AUTHORIZATION,
+                CONTENT_TYPE,
+                IF_MATCH,
+                IF_NONE_MATCH,
+                IDEMPOTENCY_KEY,
+                X_REQUEST_ID,
+                HeaderName::from_static(X_API_KEY),
+                HeaderName::from_static(X_SETUP_TOKEN),
+            ])
+            .expose_headers([
+                ETAG,
+                IDEMPOTENT_REPLAYED,
+                X_REQUEST_ID,
+                RETRY_AFTER,
+                RATELIMIT_LIMIT,
+                RATELIMIT_REMAINING,
+                RATELIMIT_RESET,
+            ])
+            .allow_credentials(config.allow_credentials)
+            .max_age(Duration::from_secs(config.max_age_secs)),
+    )
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::header::{
+    CONTENT_SECURITY_POLICY, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS,
+    X_FRAME_OPTIONS,
+};
+use axum::http::{HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::utils::cfg::SecurityConfig;
+
+/// The API only serves JSON: nothing may be loaded, framed or run from its responses.
+pub const API_CSP: &str = "default-src 'none'; frame-ancestors 'none'";
+
+/// The Scalar UI at `/docs` is a single page with inline scripts and styles which only
+/// fetches the OpenAPI document from this origin.
+pub const DOCS_CSP: &str = "default-src 'none'; script-src 'unsafe-inline'; \
+     style-src 'unsafe-inline'; connect-src 'self'; img-src 'self' data:; \
+     font-src 'self' data:; frame-ancestors 'none'; base-uri 'none'; form-action 'none'";
+
+/// Response headers computed once from the configuration.
+pub struct SecurityHeaders(Vec<(HeaderName, HeaderValue)>);
+
+impl SecurityHeaders {
+    pub fn new(config: &SecurityConfig) -> Self {
+        let mut headers = vec![
+            (X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+            (X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
+            (REFERRER_POLICY, HeaderValue::from_static("no-referrer")),
+            (CONTENT_SECURITY_POLICY, HeaderValue::from_static(API_CSP)),
+        ];
+        if config.hsts_max_age_secs > 0 {
+            let mut hsts = format!("max-age={}", config.hsts_max_age_secs);
+            if config.hsts_include_subdomains {
+                hsts.push_str("; includeSubDomains");
+            }
+            headers.push((
+                STRICT_TRANSPORT_SECURITY,
+                HeaderValue::from_str(&hsts).expect("HSTS value is ASCII"),
+            ));
+        }
+        Self(headers)
+    }
+}
+
+/// Set on the `/docs` route, before `add_security_headers` runs.
+pub async fn set_docs_csp(mut response: Response) -> Response {
+    response
+        .headers_mut()
+        .insert(CONTENT_SECURITY_POLICY, HeaderValue::from_static(DOCS_CSP));
+    response
+}
+
+/// Adds the headers the response does not set already, so a route can override one,
+/// as `/docs` does for the CSP.
+pub async fn add_security_headers(
+    State(security_headers): State<Arc<SecurityHeaders>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    for (name, value) in &security_headers.0 {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+    response
+}
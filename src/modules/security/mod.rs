@@ -0,0 +1,2 @@
+pub mod cors;
+pub mod headers;
@@ -1,11 +1,13 @@
+use std::fmt;
+
 use chrono::{DateTime, Utc};
 use tracing::trace;
 
 use crate::modules::models::entities::session::Model as SessionModel;
 use crate::modules::models::entities::user::Model as UserModel;
 use crate::modules::user::dto::UserDto;
+use crate::utils::logging::REDACTED;
 
-#[derive(Debug)]
 pub struct SessionWithUser {
     pub token: String,
     pub revoked_at: Option<DateTime<Utc>>,
@@ -24,6 +26,18 @@ impl From<(SessionModel, Option<UserModel>)> for SessionWithUser {
     }
 }
 
+/// The token is a bearer credential, keep it out of the logs.
+impl fmt::Debug for SessionWithUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionWithUser")
+            .field("token", &REDACTED)
+            .field("revoked_at", &self.revoked_at)
+            .field("expire_at", &self.expire_at)
+            .field("user", &self.user)
+            .finish()
+    }
+}
+
 impl SessionWithUser {
     pub fn is_valid(&self) -> bool {
         trace!("Revoked at : {:#?}", self.revoked_at.is_some());
@@ -1,8 +1,9 @@
+use schemars::JsonSchema;
 use serde::Serialize;
 
 use crate::modules::models::entities::session::Model as SessionModel;
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub struct SessionTokenDTO {
     pub token: String,
 }
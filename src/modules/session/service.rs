@@ -1,4 +1,5 @@
 use std::str::FromStr;
+use std::time::Duration;
 
 use crate::modules::models::entities::session::ActiveModel as SessionActiveModel;
 use crate::modules::models::entities::session::Column as SessionColumn;
@@ -6,20 +7,23 @@ use crate::modules::models::entities::session::Entity as SessionEntity;
 
 use crate::modules::models::entities::user::Entity as UserEntity;
 
-use crate::modules::errors::ServiceError;
-use crate::modules::session::dto::SessionTokenDTO;
+use crate::modules::audit::domain::{AuditAction, AuditContext, AuditResult};
+use crate::modules::audit::service::AuditService;
+use crate::modules::errors::{ErrorCode, ServiceError};
 use crate::modules::session::domain::SessionWithUser;
+use crate::modules::session::dto::SessionTokenDTO;
 use crate::modules::types::ServiceResult;
-use chrono::{Duration, Utc};
+use crate::utils::telemetry::db_span;
+use chrono::Utc;
 use migration::Expr;
 use sea_orm::ActiveValue::NotSet;
 use sea_orm::ActiveValue::Set;
 use sea_orm::ColumnTrait;
 use sea_orm::EntityTrait;
+use sea_orm::PaginatorTrait;
 use sea_orm::QueryFilter;
 use sea_orm::{ActiveModelTrait, DatabaseConnection};
-use tracing::error;
-use tracing::trace;
+use tracing::{Instrument, instrument, trace};
 use uuid::Uuid;
 
 pub struct SessionService<'a> {
@@ -31,10 +35,12 @@ impl<'a> SessionService<'a> {
         Self { db }
     }
 
-    pub async fn create(&self, user_id: i32) -> ServiceResult<SessionTokenDTO> {
+    /// Opens a session of `user_id` that expires after `ttl`.
+    #[instrument(skip(self))]
+    pub async fn create(&self, user_id: i32, ttl: Duration) -> ServiceResult<SessionTokenDTO> {
         let token = Uuid::new_v4();
         let created_at = Utc::now();
-        let expire_at = created_at + Duration::hours(720);
+        let expire_at = created_at + ttl;
 
         let session_entity = SessionActiveModel {
             token: Set(token),
@@ -46,26 +52,28 @@ impl<'a> SessionService<'a> {
 
         session_entity
             .insert(self.db)
+            .instrument(db_span("INSERT", "session"))
             .await
             .map(SessionTokenDTO::from)
             .map_err(|err| {
-                let msg = "Internal error during the creation of the session";
-                error!("message: {}\ndetails: {}", msg, err);
-                ServiceError::internal(msg).with_details(err.to_string())
+                ServiceError::from_db(err, "Internal error during the creation of the session")
             })
     }
 
+    #[instrument(skip_all)]
     pub async fn get_with_user(&self, token: &str) -> ServiceResult<SessionWithUser> {
         let token = Uuid::parse_str(token);
         match token {
             Err(err) => Err(
                 ServiceError::bad_request("The provided auth token is malformated")
+                    .with_code(ErrorCode::SessionTokenMalformed)
                     .with_details(err.to_string()),
             ),
             Ok(token) => {
                 let session_user = SessionEntity::find_by_id(token)
                     .find_also_related(UserEntity)
                     .one(self.db)
+                    .instrument(db_span("SELECT", "session"))
                     .await;
 
                 match session_user {
@@ -75,22 +83,55 @@ impl<'a> SessionService<'a> {
                             trace!("session: {:#?}", session_user_dto);
                             Ok(session_user_dto)
                         }
-                        None => Err(ServiceError::internal(
-                            "Internal error during session recovery",
-                        )),
+                        None => Err(
+                            ServiceError::unauthorized("No session found for this token")
+                                .with_code(ErrorCode::SessionNotFound),
+                        ),
                     },
-                    Err(err) => Err(ServiceError::internal(
+                    Err(err) => Err(ServiceError::from_db(
+                        err,
                         "Internal error during session recovery",
-                    )
-                    .with_details(err.to_string())),
+                    )),
                 }
             }
         }
     }
 
-    pub async fn revoke_token(&self, id: String) -> ServiceResult<SessionTokenDTO> {
-        let token_uuid = Uuid::from_str(id.as_str())
-            .map_err(|_error| ServiceError::bad_request("The given token is malformated"))?;
+    #[instrument(skip_all)]
+    pub async fn count_active(&self) -> ServiceResult<u64> {
+        let count = SessionEntity::find()
+            .filter(SessionColumn::RevokatedAt.is_null())
+            .filter(SessionColumn::ExpireAt.gt(Utc::now().fixed_offset()))
+            .count(self.db)
+            .instrument(db_span("SELECT", "session"))
+            .await?;
+        Ok(count)
+    }
+
+    #[instrument(skip_all, fields(user.id = ctx.actor_id))]
+    pub async fn revoke_token(
+        &self,
+        id: String,
+        ctx: &AuditContext,
+    ) -> ServiceResult<SessionTokenDTO> {
+        let result = self.revoke(id).await;
+
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::Logout,
+                ctx.actor_id.map(|user_id| format!("user:{}", user_id)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    async fn revoke(&self, id: String) -> ServiceResult<SessionTokenDTO> {
+        let token_uuid = Uuid::from_str(id.as_str()).map_err(|_error| {
+            ServiceError::bad_request("The given token is malformated")
+                .with_code(ErrorCode::SessionTokenMalformed)
+        })?;
 
         let result = SessionEntity::update_many()
             .col_expr(
@@ -99,12 +140,51 @@ impl<'a> SessionService<'a> {
             )
             .filter(SessionColumn::Token.eq(token_uuid))
             .exec(self.db)
+            .instrument(db_span("UPDATE", "session"))
             .await?;
 
         if result.rows_affected == 0 {
-            return Err(ServiceError::bad_request("No session founded"));
+            return Err(ServiceError::bad_request("No session founded")
+                .with_code(ErrorCode::SessionNotFound));
         }
 
         Ok(SessionTokenDTO { token: id })
     }
+
+    #[instrument(skip(self, ctx))]
+    pub async fn revoke_all_for_user(
+        &self,
+        user_id: i32,
+        ctx: &AuditContext,
+    ) -> ServiceResult<u64> {
+        let result = self.revoke_user_sessions(user_id).await;
+
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::SessionRevokeAll,
+                Some(format!("user:{}", user_id)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    /// Revokes every still active session of the user, returning how many were revoked.
+    #[instrument(skip(self))]
+    pub async fn revoke_user_sessions(&self, user_id: i32) -> ServiceResult<u64> {
+        let result = SessionEntity::update_many()
+            .col_expr(
+                SessionColumn::RevokatedAt,
+                Expr::value(Utc::now().fixed_offset()),
+            )
+            .filter(SessionColumn::UserId.eq(user_id))
+            .filter(SessionColumn::RevokatedAt.is_null())
+            .exec(self.db)
+            .instrument(db_span("UPDATE", "session"))
+            .await
+            .map_err(|err| ServiceError::from_db(err, "Failed to revoke the user sessions"))?;
+
+        Ok(result.rows_affected)
+    }
 }
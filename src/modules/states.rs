@@ -1,8 +1,93 @@
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 
 use sea_orm::DatabaseConnection;
 
+use crate::modules::idempotency::service::IdempotencyKeys;
+use crate::modules::rate_limit::service::RateLimiter;
+use crate::utils::cfg::Config;
+use crate::utils::logging::LogFilterHandle;
+
 #[derive(Clone)]
 pub struct AppState {
+    pub config: Arc<Config>,
     pub connection: Arc<DatabaseConnection>,
+    /// Read replica when configured, otherwise the primary connection.
+    pub read_connection: Arc<DatabaseConnection>,
+    pub draining: Arc<AtomicBool>,
+    pub log_filter: LogFilterHandle,
+    /// Expected `X-Setup-Token` of the root user bootstrap, `None` once users exist.
+    /// Cleared when the bootstrap is done, so the token is only usable once.
+    setup_token: Arc<RwLock<Option<Arc<str>>>>,
+    /// `None` when rate limiting is disabled.
+    pub rate_limiter: Option<Arc<RateLimiter>>,
+    /// `None` when `Idempotency-Key` headers are ignored.
+    pub idempotency: Option<Arc<IdempotencyKeys>>,
+}
+
+impl AppState {
+    pub fn new(
+        config: Arc<Config>,
+        connection: Arc<DatabaseConnection>,
+        log_filter: LogFilterHandle,
+    ) -> Self {
+        Self {
+            config,
+            read_connection: connection.clone(),
+            connection,
+            draining: Arc::new(AtomicBool::new(false)),
+            log_filter,
+            setup_token: Arc::new(RwLock::new(None)),
+            rate_limiter: None,
+            idempotency: None,
+        }
+    }
+
+    pub fn with_setup_token(mut self, setup_token: Option<String>) -> Self {
+        self.setup_token = Arc::new(RwLock::new(setup_token.map(Arc::from)));
+        self
+    }
+
+    pub fn setup_token(&self) -> Option<Arc<str>> {
+        self.setup_token
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Invalidates the setup token of this instance once the root user exists.
+    pub fn consume_setup_token(&self) {
+        *self
+            .setup_token
+            .write()
+            .unwrap_or_else(|err| err.into_inner()) = None;
+    }
+
+    pub fn with_read_replica(mut self, replica: Arc<DatabaseConnection>) -> Self {
+        self.read_connection = replica;
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    pub fn with_idempotency(mut self, idempotency: Arc<IdempotencyKeys>) -> Self {
+        self.idempotency = Some(idempotency);
+        self
+    }
+
+    pub fn has_read_replica(&self) -> bool {
+        !Arc::ptr_eq(&self.connection, &self.read_connection)
+    }
+
+    /// Flipped once shutdown starts so readiness probes take the instance out of rotation.
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
 }
@@ -0,0 +1,79 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuthMode {
+    /// Clients without a certificate still connect and authenticate with a bearer token.
+    #[default]
+    Optional,
+    /// The handshake fails without a certificate issued by the client CA.
+    Required,
+}
+
+impl FromStr for ClientAuthMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "optional" => Ok(Self::Optional),
+            "required" => Ok(Self::Required),
+            other => Err(format!(
+                "Unknown client auth mode '{}', expected optional or required",
+                other
+            )),
+        }
+    }
+}
+
+/// Certificate presented by the client and verified against the client CA during
+/// the handshake, stored in the request extensions of every request of the connection.
+#[derive(Debug, Clone)]
+pub struct ClientCertificate {
+    /// Hex encoded SHA-256 of the DER certificate.
+    pub fingerprint: String,
+    pub subject: String,
+    /// First `rfc822Name` of the subject alternative names, or the subject `emailAddress`.
+    /// This is what maps the certificate to a user.
+    pub email: Option<String>,
+}
+
+impl ClientCertificate {
+    pub fn from_der(der: &[u8]) -> Self {
+        let fingerprint = format!("{:x}", Sha256::digest(der));
+        let Ok((_, certificate)) = parse_x509_certificate(der) else {
+            return Self {
+                fingerprint,
+                subject: String::new(),
+                email: None,
+            };
+        };
+
+        let san_email = certificate
+            .subject_alternative_name()
+            .ok()
+            .flatten()
+            .and_then(|san| {
+                san.value.general_names.iter().find_map(|name| match name {
+                    GeneralName::RFC822Name(email) => Some(email.to_string()),
+                    _ => None,
+                })
+            });
+        let email = san_email.or_else(|| {
+            certificate
+                .subject()
+                .iter_email()
+                .find_map(|attribute| attribute.as_str().ok().map(str::to_owned))
+        });
+
+        Self {
+            fingerprint,
+            subject: certificate.subject().to_string(),
+            email,
+        }
+    }
+}
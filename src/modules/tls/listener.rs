@@ -0,0 +1,157 @@
+use std::convert::Infallible;
+use std::future::{Ready, ready};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::{ConnectInfo, Request};
+use axum::response::Response;
+use axum::routing::future::RouteFuture;
+use axum::serve::{IncomingStream, Listener};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tokio_rustls::server::TlsStream;
+use tower::Service;
+use tracing::{debug, error};
+
+use crate::modules::tls::domain::ClientCertificate;
+use crate::modules::tls::reload::ReloadableTls;
+
+/// A client has this long to finish the handshake before its connection is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Accepts TCP connections and runs their handshakes concurrently, so a slow client
+/// never holds back the others.
+pub struct TlsListener {
+    tcp: TcpListener,
+    tls: Arc<ReloadableTls>,
+    handshakes: JoinSet<Option<(TlsStream<TcpStream>, SocketAddr)>>,
+}
+
+impl TlsListener {
+    pub fn new(tcp: TcpListener, tls: Arc<ReloadableTls>) -> Self {
+        Self {
+            tcp,
+            tls,
+            handshakes: JoinSet::new(),
+        }
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            tokio::select! {
+                accepted = self.tcp.accept() => match accepted {
+                    Ok((stream, remote_addr)) => {
+                        let acceptor = self.tls.acceptor();
+                        self.handshakes.spawn(async move {
+                            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                                Ok(Ok(stream)) => Some((stream, remote_addr)),
+                                Ok(Err(err)) => {
+                                    debug!("TLS handshake with {} failed: {}", remote_addr, err);
+                                    None
+                                }
+                                Err(_) => {
+                                    debug!("TLS handshake with {} timed out", remote_addr);
+                                    None
+                                }
+                            }
+                        });
+                    }
+                    Err(err) => handle_accept_error(err).await,
+                },
+                Some(Ok(Some(connection))) = self.handshakes.join_next() => return connection,
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.tcp.local_addr()
+    }
+}
+
+/// Same policy as `axum::serve`: connection errors are the client's business, anything
+/// else (usually too many open files) is logged and backed off.
+async fn handle_accept_error(err: io::Error) {
+    if matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    ) {
+        return;
+    }
+    error!("Failed to accept a connection: {}", err);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+}
+
+/// The TLS counterpart of `Router::into_make_service_with_connect_info::<SocketAddr>`,
+/// also handing the verified client certificate to the requests of the connection.
+#[derive(Clone)]
+pub struct IntoMakeTlsService {
+    router: Router,
+}
+
+impl IntoMakeTlsService {
+    pub fn new(router: Router) -> Self {
+        Self { router }
+    }
+}
+
+impl Service<IncomingStream<'_, TlsListener>> for IntoMakeTlsService {
+    type Response = TlsConnectionService;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, stream: IncomingStream<'_, TlsListener>) -> Self::Future {
+        let (_, connection) = stream.io().get_ref();
+        let client_certificate = connection
+            .peer_certificates()
+            .and_then(|certificates| certificates.first())
+            .map(|certificate| ClientCertificate::from_der(certificate));
+
+        ready(Ok(TlsConnectionService {
+            router: self.router.clone(),
+            remote_addr: *stream.remote_addr(),
+            client_certificate,
+        }))
+    }
+}
+
+#[derive(Clone)]
+pub struct TlsConnectionService {
+    router: Router,
+    remote_addr: SocketAddr,
+    client_certificate: Option<ClientCertificate>,
+}
+
+impl Service<Request> for TlsConnectionService {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = RouteFuture<Infallible>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        <Router as Service<Request>>::poll_ready(&mut self.router, cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(self.remote_addr));
+        if let Some(client_certificate) = &self.client_certificate {
+            request.extensions_mut().insert(client_certificate.clone());
+        }
+        self.router.call(request)
+    }
+}
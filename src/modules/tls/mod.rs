@@ -0,0 +1,4 @@
+pub mod domain;
+pub mod listener;
+pub mod redirect;
+pub mod reload;
@@ -0,0 +1,41 @@
+use axum::Router;
+use axum::http::header::HOST;
+use axum::http::uri::{Authority, PathAndQuery};
+use axum::http::{HeaderMap, StatusCode, Uri};
+use axum::response::{IntoResponse, Redirect, Response};
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::ApiError;
+
+/// Plain HTTP app sending every request to the same host and path over HTTPS.
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        redirect_to_https(&headers, &uri, https_port)
+    })
+}
+
+fn redirect_to_https(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Response {
+    let Some(host) = headers
+        .get(HOST)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<Authority>().ok())
+    else {
+        return ApiError::new(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::BadRequest,
+            "`host` header is missing or malformed",
+        )
+        .into_response();
+    };
+    let path = uri
+        .path_and_query()
+        .map(PathAndQuery::as_str)
+        .unwrap_or("/");
+
+    // 308 keeps the method and the body, unlike 301.
+    let location = match https_port {
+        443 => format!("https://{}{}", host.host(), path),
+        port => format!("https://{}:{}{}", host.host(), port, path),
+    };
+    Redirect::permanent(&location).into_response()
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result, anyhow};
+use rustls::RootCertStore;
+use rustls::ServerConfig;
+use rustls::crypto::ring::default_provider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::modules::tls::domain::ClientAuthMode;
+use crate::utils::cfg::TlsConfig;
+
+/// Protocols offered through ALPN, HTTP/2 first.
+const ALPN_PROTOCOLS: [&[u8]; 2] = [b"h2", b"http/1.1"];
+
+/// rustls configuration rebuilt whenever one of its PEM files changes on disk.
+/// Connections keep the configuration they were accepted with.
+pub struct ReloadableTls {
+    config: TlsConfig,
+    current: RwLock<Arc<ServerConfig>>,
+    modified: Mutex<Vec<Option<SystemTime>>>,
+}
+
+impl ReloadableTls {
+    pub fn load(config: &TlsConfig) -> Result<Self> {
+        let modified = modified_times(&watched_files(config));
+        let server_config = server_config(config)?;
+        Ok(Self {
+            config: config.clone(),
+            current: RwLock::new(Arc::new(server_config)),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    pub fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(
+            self.current
+                .read()
+                .unwrap_or_else(|err| err.into_inner())
+                .clone(),
+        )
+    }
+
+    /// Returns whether a new configuration is in use. A broken one is rejected and the
+    /// previous configuration keeps serving.
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let modified = modified_times(&watched_files(&self.config));
+        let mut last_modified = self.modified.lock().unwrap_or_else(|err| err.into_inner());
+        if *last_modified == modified {
+            return Ok(false);
+        }
+        // Retried on the next tick only if the files change again.
+        *last_modified = modified;
+
+        let server_config = server_config(&self.config)?;
+        *self.current.write().unwrap_or_else(|err| err.into_inner()) = Arc::new(server_config);
+        Ok(true)
+    }
+
+    /// Background task polling the PEM files until `shutdown` fires.
+    pub async fn watch(self: Arc<Self>, shutdown: CancellationToken) {
+        let Some(period) = self.config.reload_interval() else {
+            return;
+        };
+        let mut interval = tokio::time::interval(period);
+        interval.tick().await;
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                _ = interval.tick() => match self.reload_if_changed() {
+                    Ok(true) => info!("TLS certificates reloaded"),
+                    Ok(false) => {}
+                    Err(err) => warn!("Keeping the previous TLS certificates: {:#}", err),
+                },
+            }
+        }
+    }
+}
+
+fn watched_files(config: &TlsConfig) -> Vec<&PathBuf> {
+    [&config.cert_path, &config.key_path, &config.client_ca_path]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn modified_times(paths: &[&PathBuf]) -> Vec<Option<SystemTime>> {
+    // `metadata` follows symlinks, so swapped Kubernetes secret volumes are noticed too.
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        .collect()
+}
+
+fn server_config(config: &TlsConfig) -> Result<ServerConfig> {
+    let (Some(cert_path), Some(key_path)) = (&config.cert_path, &config.key_path) else {
+        return Err(anyhow!("TLS needs both a certificate and a private key"));
+    };
+    let certificates = read_certificates(cert_path)?;
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .with_context(|| format!("Cannot read the private key {}", key_path.display()))?;
+
+    let provider = Arc::new(default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?;
+    let builder = match &config.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for certificate in read_certificates(ca_path)? {
+                roots
+                    .add(certificate)
+                    .with_context(|| format!("Invalid client CA in {}", ca_path.display()))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = match config.client_auth {
+                ClientAuthMode::Optional => verifier.allow_unauthenticated(),
+                ClientAuthMode::Required => verifier,
+            };
+            builder.with_client_cert_verifier(verifier.build()?)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder
+        .with_single_cert(certificates, key)
+        .context("The certificate does not match the private key")?;
+    server_config.alpn_protocols = ALPN_PROTOCOLS.iter().map(|p| p.to_vec()).collect();
+    Ok(server_config)
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certificates = CertificateDer::pem_file_iter(path)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Cannot read the certificates {}", path.display()))?;
+    if certificates.is_empty() {
+        return Err(anyhow!("No certificate found in {}", path.display()));
+    }
+    Ok(certificates)
+}
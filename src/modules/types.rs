@@ -1,7 +1,6 @@
 use crate::modules::errors::ServiceError;
-use crate::modules::responses::ApiError;
-use axum::Json;
+use crate::modules::responses::{ApiError, Negotiated};
 use axum::response::Result;
 
-pub type ApiResponse<T> = Result<Json<T>, ApiError>;
+pub type ApiResponse<T> = Result<Negotiated<T>, ApiError>;
 pub type ServiceResult<T> = std::result::Result<T, ServiceError>;
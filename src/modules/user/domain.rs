@@ -0,0 +1,14 @@
+/// Role name checked by the admin-only endpoints.
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Granted to the root user created by the bootstrap, implies `ADMIN_ROLE`.
+pub const SUPER_ADMIN_ROLE: &str = "super_admin";
+
+/// Roles accepted by the admin-only endpoints.
+pub const ADMIN_ROLES: [&str; 2] = [ADMIN_ROLE, SUPER_ADMIN_ROLE];
+
+/// Header carrying the one-time setup token of the root user bootstrap.
+pub const X_SETUP_TOKEN: &str = "x-setup-token";
+
+/// Serializes concurrent bootstraps, see `UserService::create_initial_root_user`.
+pub const BOOTSTRAP_LOCK_SQL: &str = "SELECT pg_advisory_xact_lock(7358240116902381666)";
@@ -1,12 +1,26 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::Serialize;
 
+use crate::modules::models::entities::role::Model as RoleModel;
 use crate::modules::models::entities::user::Model as UserModel;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct UserDto {
     pub id: i32,
     pub name: String,
     pub email: String,
+    pub disabled_at: Option<DateTime<Utc>>,
+    /// Bumped on every update, sent back to update the user.
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserDto {
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
 }
 
 impl From<UserModel> for UserDto {
@@ -15,6 +29,35 @@ impl From<UserModel> for UserDto {
             id: user.id,
             name: user.name,
             email: user.email,
+            disabled_at: user.disabled_at.map(|dt| dt.to_utc()),
+            version: user.version,
+            created_at: user.created_at.to_utc(),
+            updated_at: user.updated_at.to_utc(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RoleDto {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub user_id: i32,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<RoleModel> for RoleDto {
+    fn from(role: RoleModel) -> Self {
+        RoleDto {
+            id: role.id,
+            name: role.name,
+            description: role.description,
+            user_id: role.user_id,
+            version: role.version,
+            created_at: role.created_at.to_utc(),
+            updated_at: role.updated_at.to_utc(),
         }
     }
 }
@@ -0,0 +1,85 @@
+use aide::OperationInput;
+use aide::generate::GenContext;
+use aide::openapi::{
+    HeaderStyle, Operation, Parameter, ParameterData, ParameterSchemaOrContent, ReferenceOr,
+    SchemaObject,
+};
+use axum::extract::FromRequestParts;
+use axum::http::StatusCode;
+use axum::http::request::Parts;
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::ApiError;
+use crate::modules::states::AppState;
+use crate::modules::user::domain::X_SETUP_TOKEN;
+
+/// Guards the root user bootstrap: the request must carry the setup token of the instance.
+pub struct ExtractSetupToken;
+
+impl FromRequestParts<AppState> for ExtractSetupToken {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        app_state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let expected = app_state.setup_token().ok_or(ApiError::new(
+            StatusCode::NOT_FOUND,
+            ErrorCode::SetupUnavailable,
+            "No longer available",
+        ))?;
+
+        let provided = parts.headers.get(X_SETUP_TOKEN).ok_or(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            ErrorCode::SetupTokenInvalid,
+            "`x-setup-token` header is missing",
+        ))?;
+
+        if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                ErrorCode::SetupTokenInvalid,
+                "The setup token is invalid",
+            ));
+        }
+        Ok(ExtractSetupToken)
+    }
+}
+
+/// Compares without returning early, so the response time does not leak the matching prefix.
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+impl OperationInput for ExtractSetupToken {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        let json_schema = ctx.schema.subschema_for::<String>();
+        operation
+            .parameters
+            .push(ReferenceOr::Item(Parameter::Header {
+                parameter_data: ParameterData {
+                    name: X_SETUP_TOKEN.to_owned(),
+                    description: Some(
+                        "One-time token from the configuration or the startup logs".to_owned(),
+                    ),
+                    required: true,
+                    deprecated: None,
+                    format: ParameterSchemaOrContent::Schema(SchemaObject {
+                        json_schema,
+                        example: None,
+                        external_docs: None,
+                    }),
+                    example: None,
+                    examples: Default::default(),
+                    explode: None,
+                    extensions: Default::default(),
+                },
+                style: HeaderStyle::Simple,
+            }));
+    }
+}
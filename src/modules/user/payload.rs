@@ -1,11 +1,24 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer};
-use validator::Validate;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::modules::responses::ApiError;
+use crate::modules::states::AppState;
+use crate::modules::user::service::UserService;
+use crate::utils::extractor::AsyncValidate;
 
 fn deserialize_lowercase<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
     String::deserialize(deserializer).map(|s| s.to_lowercase())
 }
 
-#[derive(Deserialize, Validate)]
+fn deserialize_optional_lowercase<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Option::<String>::deserialize(deserializer).map(|s| s.map(|s| s.to_lowercase()))
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
 pub struct CreateUser {
     #[validate(length(min = 1, message = "Name cannot be empty"))]
     pub name: String,
@@ -16,7 +29,44 @@ pub struct CreateUser {
     pub password: String,
 }
 
-#[derive(Deserialize, Validate)]
+/// The email must not belong to another user. The insert still reports a concurrent
+/// signup with the same email as a conflict.
+impl AsyncValidate for CreateUser {
+    async fn validate_async(&self, state: &AppState) -> Result<(), ApiError> {
+        if UserService::new(&state.connection)
+            .is_email_taken(&self.email)
+            .await?
+        {
+            let mut errors = ValidationErrors::new();
+            errors.add(
+                "email",
+                ValidationError::new("taken").with_message("Email already taken".into()),
+            );
+            return Err(errors.into());
+        }
+        Ok(())
+    }
+}
+
+/// Omitted fields are kept. `version` is the one the user was read with, `If-Match` can
+/// carry its ETag instead.
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct UpdateUser {
+    #[validate(length(min = 1, message = "Name cannot be empty"))]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_optional_lowercase")]
+    #[validate(email(message = "Invalid email address"))]
+    pub email: Option<String>,
+    pub version: Option<i32>,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct ResetPasswordPayload {
+    #[validate(length(min = 8, message = "Password must be at least 8 characters"))]
+    pub password: String,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
 pub struct LoginPayload {
     #[serde(deserialize_with = "deserialize_lowercase")]
     #[validate(email(message = "Invalid email address"))]
@@ -24,3 +74,40 @@ pub struct LoginPayload {
     #[validate(length(min = 1, message = "Password cannot be empty"))]
     pub password: String,
 }
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct RolePayload {
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Role name must be between 1 and 64 characters"
+    ))]
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Default, Deserialize, JsonSchema)]
+pub struct UserListQuery {
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    pub updated_from: Option<DateTime<Utc>>,
+    pub updated_to: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct UserIdPath {
+    #[validate(range(min = 1, message = "User id must be positive"))]
+    pub id: i32,
+}
+
+#[derive(Deserialize, Validate, JsonSchema)]
+pub struct UserRolePath {
+    #[validate(range(min = 1, message = "User id must be positive"))]
+    pub id: i32,
+    #[validate(length(
+        min = 1,
+        max = 64,
+        message = "Role name must be between 1 and 64 characters"
+    ))]
+    pub name: String,
+}
@@ -1,59 +1,209 @@
-use axum::extract::{Path, State};
-use axum::routing::{delete, get, post};
-use axum::{Json, Router};
+use aide::axum::ApiRouter;
+use aide::axum::routing::{delete_with, get_with, post_with};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::NoContent;
 
-use crate::modules::auth::extractor::ExtractAuthInfos;
-use crate::modules::responses::ApiError;
+use crate::modules::audit::extractor::ExtractAuditContext;
+use crate::modules::auth::extractor::{ExtractAdminInfos, ExtractAuthInfos};
+use crate::modules::conditional::extractor::{ExtractIfMatch, precondition_failed};
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::{ApiError, Negotiated};
 use crate::modules::states::AppState;
 use crate::modules::types::ApiResponse;
-use crate::modules::user::dto::UserDto;
-use crate::modules::user::payload::CreateUser;
+use crate::modules::user::dto::{RoleDto, UserDto};
+use crate::modules::user::extractor::ExtractSetupToken;
+use crate::modules::user::payload::{
+    CreateUser, RolePayload, UpdateUser, UserIdPath, UserListQuery, UserRolePath,
+};
 use crate::modules::user::service::UserService;
-use crate::utils::extractor::ExtractValidated;
+use crate::utils::extractor::{
+    ExtractAsyncValidated, ExtractQuery, ExtractValidated, ExtractValidatedPath,
+};
 
-pub fn user_router() -> Router<AppState> {
-    Router::new()
-        .route("/", get(handle_get_users))
-        .route("/", post(handle_create_user))
-        .route("/initial_root_user", post(handle_create_initial_root_user))
-        .route("/{id}", delete(handle_delete_user))
+pub fn user_router() -> ApiRouter<AppState> {
+    ApiRouter::new()
+        .api_route(
+            "/",
+            get_with(handle_get_users, |op| op.tag("users").summary("List users"))
+                .post_with(handle_create_user, |op| {
+                    op.tag("users").summary("Create a user")
+                }),
+        )
+        .api_route(
+            "/initial_root_user",
+            post_with(handle_create_initial_root_user, |op| {
+                op.tag("users")
+                    .summary("Create the super admin of an empty instance (setup token)")
+            }),
+        )
+        .api_route(
+            "/{id}",
+            get_with(handle_get_user, |op| op.tag("users").summary("Get a user"))
+                .patch_with(handle_update_user, |op| {
+                    op.tag("users")
+                        .summary("Update a user at a known version (admin only)")
+                })
+                .delete_with(handle_delete_user, |op| {
+                    op.tag("users").summary("Delete a user")
+                }),
+        )
+        .api_route(
+            "/{id}/roles",
+            post_with(handle_grant_role, |op| {
+                op.tag("users")
+                    .summary("Grant a role to a user (admin only)")
+            }),
+        )
+        .api_route(
+            "/{id}/roles/{name}",
+            delete_with(handle_revoke_role, |op| {
+                op.tag("users")
+                    .summary("Revoke a role from a user (admin only)")
+            }),
+        )
 }
 
 async fn handle_create_initial_root_user(
     state: State<AppState>,
+    _: ExtractSetupToken,
+    ExtractAuditContext(ctx): ExtractAuditContext,
     ExtractValidated(payload): ExtractValidated<CreateUser>,
 ) -> ApiResponse<UserDto> {
     let user_svc = UserService::new(&state.connection);
+    let result = user_svc.create_initial_root_user(payload, &ctx).await;
+    // Whether this call or another replica created the root user, the token is spent.
+    let spent = match &result {
+        Ok(_) => true,
+        Err(err) => err.code == ErrorCode::SetupUnavailable,
+    };
+    if spent {
+        state.consume_setup_token();
+    }
+    result.map(Negotiated).map_err(ApiError::from)
+}
+
+async fn handle_get_users(
+    State(state): State<AppState>,
+    ExtractQuery(query): ExtractQuery<UserListQuery>,
+) -> ApiResponse<Vec<UserDto>> {
+    let user_svc = UserService::new(&state.read_connection);
+    user_svc
+        .get_all(&query)
+        .await
+        .map(Negotiated)
+        .map_err(ApiError::from)
+}
+
+async fn handle_get_user(
+    State(state): State<AppState>,
+    _: ExtractAuthInfos,
+    ExtractValidatedPath(UserIdPath { id }): ExtractValidatedPath<UserIdPath>,
+) -> ApiResponse<UserDto> {
+    let user_svc = UserService::new(&state.read_connection);
     user_svc
-        .create_initial_root_user(payload)
+        .get_one(id)
         .await
-        .map(Json)
+        .map(Negotiated)
         .map_err(ApiError::from)
 }
 
-async fn handle_get_users(State(state): State<AppState>) -> ApiResponse<Vec<UserDto>> {
+async fn handle_update_user(
+    State(state): State<AppState>,
+    ExtractAdminInfos(auth_session): ExtractAdminInfos,
+    ExtractAuditContext(ctx): ExtractAuditContext,
+    ExtractValidatedPath(UserIdPath { id }): ExtractValidatedPath<UserIdPath>,
+    if_match: ExtractIfMatch,
+    ExtractValidated(payload): ExtractValidated<UpdateUser>,
+) -> ApiResponse<UserDto> {
+    let ctx = ctx.with_actor(auth_session.user.id);
     let user_svc = UserService::new(&state.connection);
-    user_svc.get_all().await.map(Json).map_err(ApiError::from)
+
+    // Either way a version mismatch answers 409 with the user as it is now. `If-Match` is
+    // checked against the current user even when the body carries a `version`.
+    let version = match (payload.version, if_match.0.is_some()) {
+        (version, true) => {
+            let current = user_svc.get_one(id).await?;
+            if !if_match.allows(&current) {
+                return Err(UserService::version_conflict(&current).into());
+            }
+            version.unwrap_or(current.version)
+        }
+        (Some(version), false) => version,
+        (None, false) => {
+            return Err(ApiError::new(
+                StatusCode::PRECONDITION_REQUIRED,
+                ErrorCode::PreconditionRequired,
+                "Send the `version` the user was read with, or its ETag in `if-match`",
+            ));
+        }
+    };
+
+    user_svc
+        .update(id, payload, version, &ctx)
+        .await
+        .map(Negotiated)
+        .map_err(ApiError::from)
 }
 
 async fn handle_delete_user(
     State(state): State<AppState>,
-    ExtractAuthInfos(_): ExtractAuthInfos,
-    Path(id): Path<i32>,
+    ExtractAuthInfos(auth_session): ExtractAuthInfos,
+    ExtractAuditContext(ctx): ExtractAuditContext,
+    ExtractValidatedPath(UserIdPath { id }): ExtractValidatedPath<UserIdPath>,
+    if_match: ExtractIfMatch,
 ) -> ApiResponse<UserDto> {
+    let ctx = ctx.with_actor(auth_session.user.id);
     let user_svc = UserService::new(&state.connection);
-    user_svc.delete(id).await.map(Json).map_err(ApiError::from)
+    if if_match.0.is_some() && !if_match.allows(&user_svc.get_one(id).await?) {
+        return Err(precondition_failed());
+    }
+    user_svc
+        .delete(id, &ctx)
+        .await
+        .map(Negotiated)
+        .map_err(ApiError::from)
 }
 
 async fn handle_create_user(
     State(state): State<AppState>,
-    ExtractAuthInfos(_): ExtractAuthInfos,
-    ExtractValidated(payload): ExtractValidated<CreateUser>,
+    ExtractAuthInfos(auth_session): ExtractAuthInfos,
+    ExtractAuditContext(ctx): ExtractAuditContext,
+    ExtractAsyncValidated(payload): ExtractAsyncValidated<CreateUser>,
 ) -> ApiResponse<UserDto> {
+    let ctx = ctx.with_actor(auth_session.user.id);
+    let user_svc = UserService::new(&state.connection);
+    user_svc
+        .create(payload, &ctx)
+        .await
+        .map(Negotiated)
+        .map_err(ApiError::from)
+}
+
+async fn handle_grant_role(
+    State(state): State<AppState>,
+    ExtractAdminInfos(auth_session): ExtractAdminInfos,
+    ExtractAuditContext(ctx): ExtractAuditContext,
+    ExtractValidatedPath(UserIdPath { id }): ExtractValidatedPath<UserIdPath>,
+    ExtractValidated(payload): ExtractValidated<RolePayload>,
+) -> ApiResponse<RoleDto> {
+    let ctx = ctx.with_actor(auth_session.user.id);
     let user_svc = UserService::new(&state.connection);
     user_svc
-        .create(payload)
+        .grant_role(id, payload, &ctx)
         .await
-        .map(Json)
+        .map(Negotiated)
         .map_err(ApiError::from)
 }
+
+async fn handle_revoke_role(
+    State(state): State<AppState>,
+    ExtractAdminInfos(auth_session): ExtractAdminInfos,
+    ExtractAuditContext(ctx): ExtractAuditContext,
+    ExtractValidatedPath(UserRolePath { id, name }): ExtractValidatedPath<UserRolePath>,
+) -> Result<NoContent, ApiError> {
+    let ctx = ctx.with_actor(auth_session.user.id);
+    let user_svc = UserService::new(&state.connection);
+    user_svc.revoke_role(id, &name, &ctx).await?;
+    Ok(NoContent)
+}
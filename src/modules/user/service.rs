@@ -1,117 +1,567 @@
+use std::time::Instant;
+
 use argon2::{
     Argon2,
     password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
 };
-use sea_orm::EntityTrait;
-use sea_orm::{ActiveModelTrait, ActiveValue};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelBehavior, ActiveModelTrait, ActiveValue, DbErr, IntoActiveModel, QueryOrder,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use sea_orm::{ConnectionTrait, TransactionTrait};
 use sea_orm::{DatabaseConnection, PaginatorTrait};
+use tracing::{Instrument, info_span, instrument};
 
+use crate::modules::models::entities::role::ActiveModel as RoleActiveModel;
+use crate::modules::models::entities::role::Column as RoleColumn;
+use crate::modules::models::entities::role::Entity as RoleEntity;
 use crate::modules::models::entities::user::ActiveModel as UserActiveModel;
+use crate::modules::models::entities::user::Column as UserColumn;
 use crate::modules::models::entities::user::Entity as UserEntity;
 use crate::modules::models::entities::user::Model as UserModel;
 
-use crate::modules::errors::ServiceError;
+use crate::modules::audit::domain::{AuditAction, AuditContext, AuditResult, user_target};
+use crate::modules::audit::service::AuditService;
+use crate::modules::errors::{ErrorCode, ServiceError};
+use crate::modules::metrics::recorder::record_argon2_duration;
+use crate::modules::session::service::SessionService;
 use crate::modules::types::ServiceResult;
-use crate::modules::user::dto::UserDto;
-use crate::modules::user::payload::CreateUser;
+use crate::modules::user::domain::{ADMIN_ROLES, BOOTSTRAP_LOCK_SQL, SUPER_ADMIN_ROLE};
+use crate::modules::user::dto::{RoleDto, UserDto};
+use crate::modules::user::payload::{
+    CreateUser, ResetPasswordPayload, RolePayload, UpdateUser, UserListQuery,
+};
+use crate::utils::logging::email_digest;
+use crate::utils::telemetry::db_span;
 
 pub struct UserService<'a> {
     db: &'a DatabaseConnection,
-    argon2: Argon2<'a>,
 }
 
 impl<'a> UserService<'a> {
     pub fn new(db: &'a DatabaseConnection) -> Self {
-        Self {
-            db,
-            argon2: Argon2::default(),
-        }
+        Self { db }
+    }
+
+    /// Argon2 is CPU bound, so hashing runs on the blocking pool.
+    async fn hash_password(password: String) -> ServiceResult<String> {
+        // Entered inside the closure, like `argon2.verify`, so that it covers the hashing.
+        let span = info_span!("argon2.hash");
+        tokio::task::spawn_blocking(move || {
+            let _guard = span.enter();
+            let salt = SaltString::generate(&mut OsRng);
+            let started_at = Instant::now();
+            let hash = Argon2::default().hash_password(password.as_bytes(), &salt);
+            record_argon2_duration("hash", started_at);
+
+            hash.map(|hash| hash.to_string()).map_err(|err| {
+                ServiceError::internal("internal error").with_details(err.to_string())
+            })
+        })
+        .await
+        .map_err(|_| ServiceError::internal("Password hashing task failed"))?
     }
 
-    pub async fn create(&self, payload: CreateUser) -> ServiceResult<UserDto> {
-        let salt = SaltString::generate(&mut OsRng);
-        let password_hash = self
-            .argon2
-            .hash_password(payload.password.as_bytes(), &salt)
-            .map_err(|err| ServiceError::internal("internal error").with_details(err.to_string()))?
-            .to_string();
+    #[instrument(skip_all, fields(user.email_digest = %email_digest(&payload.email)))]
+    pub async fn create(&self, payload: CreateUser, ctx: &AuditContext) -> ServiceResult<UserDto> {
+        let email = payload.email.clone();
+        let result = self.insert(payload).await;
 
+        let user_id = result.as_ref().ok().map(|user| user.id);
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::UserCreate,
+                Some(user_target(user_id, &email)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    async fn insert(&self, payload: CreateUser) -> ServiceResult<UserDto> {
+        let password_hash = Self::hash_password(payload.password).await?;
+        Self::insert_user(self.db, payload.name, payload.email, password_hash)
+            .await
+            .map(UserDto::from)
+    }
+
+    async fn insert_user<C: ConnectionTrait>(
+        db: &C,
+        name: String,
+        email: String,
+        password_hash: String,
+    ) -> ServiceResult<UserModel> {
         let new_user = UserActiveModel {
-            name: ActiveValue::Set(payload.name),
-            email: ActiveValue::Set(payload.email),
+            name: ActiveValue::Set(name),
+            email: ActiveValue::Set(email),
             password: ActiveValue::Set(password_hash),
             ..Default::default()
         };
 
-        let created_user = new_user.insert(self.db).await.map_err(|e| {
-            ServiceError::internal("Failed to create user").with_details(e.to_string())
-        })?;
+        new_user
+            .insert(db)
+            .instrument(db_span("INSERT", "user"))
+            .await
+            .map_err(|e| Self::email_taken(ServiceError::from_db(e, "Failed to create user")))
+    }
 
-        Ok(UserDto {
-            id: created_user.id,
-            name: created_user.name,
-            email: created_user.email,
-        })
+    /// The unique violation on `email` of an insert or an update, told apart from the others.
+    fn email_taken(err: ServiceError) -> ServiceError {
+        match (err.code, err.field.as_deref()) {
+            (ErrorCode::UniqueViolation, Some("email")) => err
+                .with_code(ErrorCode::UserEmailTaken)
+                .with_message("This email is already used by another account"),
+            _ => err,
+        }
+    }
+
+    /// Creates the first user of an empty instance with the `SUPER_ADMIN_ROLE`. Any call once
+    /// a user exists fails with `SETUP_UNAVAILABLE`.
+    #[instrument(skip_all, fields(user.email_digest = %email_digest(&payload.email)))]
+    pub async fn create_initial_root_user(
+        &self,
+        payload: CreateUser,
+        ctx: &AuditContext,
+    ) -> ServiceResult<UserDto> {
+        let email = payload.email.clone();
+        let result = self.bootstrap(payload).await;
+
+        let user_id = result.as_ref().ok().map(|user| user.id);
+        let ctx = match user_id {
+            Some(user_id) => ctx.clone().with_actor(user_id),
+            None => ctx.clone(),
+        };
+        AuditService::new(self.db)
+            .record(
+                &ctx,
+                AuditAction::UserBootstrap,
+                Some(user_target(user_id, &email)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
     }
 
-    pub async fn create_initial_root_user(&self, payload: CreateUser) -> ServiceResult<UserDto> {
-        let users_count = self.get_activated_users_count().await?;
+    /// The advisory lock serializes concurrent calls, so only one of them can see the
+    /// user table empty, and the user and its role are committed together.
+    async fn bootstrap(&self, payload: CreateUser) -> ServiceResult<UserDto> {
+        // Argon2 is slow, hash before taking the lock.
+        let password_hash = Self::hash_password(payload.password).await?;
+
+        let txn = self
+            .db
+            .begin()
+            .await
+            .map_err(|e| ServiceError::from_db(e, "Failed to start the bootstrap"))?;
+        txn.execute_unprepared(BOOTSTRAP_LOCK_SQL)
+            .instrument(db_span("SELECT", "pg_advisory_xact_lock"))
+            .await
+            .map_err(|e| ServiceError::from_db(e, "Failed to lock the bootstrap"))?;
 
+        let users_count = UserEntity::find()
+            .count(&txn)
+            .instrument(db_span("SELECT", "user"))
+            .await?;
         if users_count > 0 {
-            Err(ServiceError::not_found("No longer available"))
-        } else {
-            Ok(self.create(payload).await?)
+            return Err(ServiceError::not_found("No longer available")
+                .with_code(ErrorCode::SetupUnavailable));
+        }
+
+        let user = Self::insert_user(&txn, payload.name, payload.email, password_hash).await?;
+        RoleActiveModel {
+            name: ActiveValue::Set(SUPER_ADMIN_ROLE.to_owned()),
+            description: ActiveValue::Set(Some("Initial root user".to_owned())),
+            user_id: ActiveValue::Set(user.id),
+            ..Default::default()
         }
+        .insert(&txn)
+        .instrument(db_span("INSERT", "role"))
+        .await
+        .map_err(|e| ServiceError::from_db(e, "Failed to grant role"))?;
+
+        txn.commit()
+            .await
+            .map_err(|e| ServiceError::from_db(e, "Failed to commit the bootstrap"))?;
+        Ok(UserDto::from(user))
     }
 
+    #[instrument(skip_all)]
     pub async fn get_activated_users_count(&self) -> ServiceResult<u64> {
-        UserEntity::find().count(self.db).await.map(Ok)?
+        UserEntity::find()
+            .count(self.db)
+            .instrument(db_span("SELECT", "user"))
+            .await
+            .map(Ok)?
     }
 
-    pub async fn get_all(&self) -> ServiceResult<Vec<UserDto>> {
-        let users = UserEntity::find().all(self.db).await.map_err(|e| {
-            ServiceError::internal("Failed to fetch users").with_details(e.to_string())
-        })?;
+    #[instrument(skip_all)]
+    pub async fn get_all(&self, query: &UserListQuery) -> ServiceResult<Vec<UserDto>> {
+        let mut select = UserEntity::find().order_by_asc(UserColumn::Id);
+        if let Some(from) = query.created_from {
+            select = select.filter(UserColumn::CreatedAt.gte(from.fixed_offset()));
+        }
+        if let Some(to) = query.created_to {
+            select = select.filter(UserColumn::CreatedAt.lt(to.fixed_offset()));
+        }
+        if let Some(from) = query.updated_from {
+            select = select.filter(UserColumn::UpdatedAt.gte(from.fixed_offset()));
+        }
+        if let Some(to) = query.updated_to {
+            select = select.filter(UserColumn::UpdatedAt.lt(to.fixed_offset()));
+        }
+
+        let users = select
+            .all(self.db)
+            .instrument(db_span("SELECT", "user"))
+            .await
+            .map_err(|e| ServiceError::from_db(e, "Failed to fetch users"))?;
 
         Ok(users.into_iter().map(UserDto::from).collect())
     }
 
+    #[instrument(skip(self))]
     pub async fn get_one(&self, id: i32) -> ServiceResult<UserDto> {
-        let user = UserEntity::find_by_id(id)
-            .one(self.db)
+        self.get_one_model(id).await.map(UserDto::from)
+    }
+
+    /// Applies `payload` only if the user is still at `version`, otherwise answers a
+    /// conflict carrying the user as it is now.
+    #[instrument(skip(self, payload, ctx))]
+    pub async fn update(
+        &self,
+        id: i32,
+        payload: UpdateUser,
+        version: i32,
+        ctx: &AuditContext,
+    ) -> ServiceResult<UserDto> {
+        let result = self.update_if_current(id, payload, version).await;
+
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::UserUpdate,
+                Some(format!("user:{}", id)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    async fn update_if_current(
+        &self,
+        id: i32,
+        payload: UpdateUser,
+        version: i32,
+    ) -> ServiceResult<UserDto> {
+        let mut user = UserActiveModel {
+            id: ActiveValue::Unchanged(id),
+            version: ActiveValue::Unchanged(version),
+            ..Default::default()
+        };
+        if let Some(name) = payload.name {
+            user.name = ActiveValue::Set(name);
+        }
+        if let Some(email) = payload.email {
+            user.email = ActiveValue::Set(email);
+        }
+
+        // `before_save` bumps the version, the filter makes the write a compare-and-swap.
+        let user = user
+            .before_save(self.db, false)
             .await
-            .map_err(|err| {
-                ServiceError::internal("Failed to fetch users").with_details(err.to_string())
-            })?;
+            .map_err(|e| ServiceError::from_db(e, "Failed to update user"))?;
+        let updated = UserEntity::update(user)
+            .validate()
+            .map_err(|e| ServiceError::from_db(e, "Failed to update user"))?
+            .filter(UserColumn::Version.eq(version))
+            .exec(self.db)
+            .instrument(db_span("UPDATE", "user"))
+            .await;
+
+        match updated {
+            Ok(user) => Ok(UserDto::from(user)),
+            Err(DbErr::RecordNotUpdated) => Err(Self::version_conflict(&self.get_one(id).await?)),
+            Err(e) => Err(Self::email_taken(ServiceError::from_db(
+                e,
+                "Failed to update user",
+            ))),
+        }
+    }
+
+    pub fn version_conflict(current: &UserDto) -> ServiceError {
+        ServiceError::conflict(format!(
+            "The user {} changed since it was read, it is now at version {}",
+            current.id, current.version
+        ))
+        .with_code(ErrorCode::VersionConflict)
+        .with_current(current)
+    }
+
+    #[instrument(skip(self, ctx))]
+    pub async fn delete(&self, id: i32, ctx: &AuditContext) -> ServiceResult<UserDto> {
+        let result = self.delete_by_id(id).await;
 
-        user.map(UserDto::from)
-            .ok_or_else(|| ServiceError::not_found(format!("The user with id {}", id)))
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::UserDelete,
+                Some(format!("user:{}", id)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
     }
 
-    pub async fn delete(&self, id: i32) -> ServiceResult<UserDto> {
+    async fn delete_by_id(&self, id: i32) -> ServiceResult<UserDto> {
         let deleted_user = UserEntity::delete_by_id(id)
             .exec_with_returning(self.db)
+            .instrument(db_span("DELETE", "user"))
             .await
             .map_err(|e| {
-                ServiceError::internal(format!("Error during delete of user with id: {}", id))
-                    .with_details(e.to_string())
+                ServiceError::from_db(e, format!("Error during delete of user with id: {}", id))
             })?;
 
-        deleted_user
-            .map(UserDto::from)
-            .ok_or_else(|| ServiceError::not_found(format!("User with id {} not found", id)))
+        deleted_user.map(UserDto::from).ok_or_else(|| {
+            ServiceError::not_found(format!("User with id {} not found", id))
+                .with_code(ErrorCode::UserNotFound)
+        })
     }
 
+    async fn get_one_model(&self, id: i32) -> ServiceResult<UserModel> {
+        let user = UserEntity::find_by_id(id)
+            .one(self.db)
+            .instrument(db_span("SELECT", "user"))
+            .await
+            .map_err(|err| ServiceError::from_db(err, "Failed to fetch users"))?;
+
+        user.ok_or_else(|| {
+            ServiceError::not_found(format!("The user with id {}", id))
+                .with_code(ErrorCode::UserNotFound)
+        })
+    }
+
+    /// Blocks the login of the user and revokes its sessions. Disabling twice keeps
+    /// the original date.
+    #[instrument(skip(self, ctx))]
+    pub async fn disable(&self, id: i32, ctx: &AuditContext) -> ServiceResult<UserDto> {
+        let result = self.set_disabled(id).await;
+
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::UserDisable,
+                Some(format!("user:{}", id)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    async fn set_disabled(&self, id: i32) -> ServiceResult<UserDto> {
+        let user = self.get_one_model(id).await?;
+        let user = if user.disabled_at.is_some() {
+            user
+        } else {
+            let mut user = user.into_active_model();
+            user.disabled_at = ActiveValue::Set(Some(Utc::now().fixed_offset()));
+            user.update(self.db)
+                .instrument(db_span("UPDATE", "user"))
+                .await
+                .map_err(|e| ServiceError::from_db(e, "Failed to disable user"))?
+        };
+
+        SessionService::new(self.db)
+            .revoke_user_sessions(id)
+            .await?;
+        Ok(UserDto::from(user))
+    }
+
+    /// Replaces the password and revokes every session opened with the previous one.
+    #[instrument(skip(self, payload, ctx))]
+    pub async fn reset_password(
+        &self,
+        id: i32,
+        payload: ResetPasswordPayload,
+        ctx: &AuditContext,
+    ) -> ServiceResult<UserDto> {
+        let result = self.update_password(id, payload).await;
+
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::UserPasswordReset,
+                Some(format!("user:{}", id)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    async fn update_password(
+        &self,
+        id: i32,
+        payload: ResetPasswordPayload,
+    ) -> ServiceResult<UserDto> {
+        let user = self.get_one_model(id).await?;
+        let password_hash = Self::hash_password(payload.password).await?;
+
+        let mut user = user.into_active_model();
+        user.password = ActiveValue::Set(password_hash);
+        let user = user
+            .update(self.db)
+            .instrument(db_span("UPDATE", "user"))
+            .await
+            .map_err(|e| ServiceError::from_db(e, "Failed to reset the password"))?;
+
+        SessionService::new(self.db)
+            .revoke_user_sessions(id)
+            .await?;
+        Ok(UserDto::from(user))
+    }
+
+    #[instrument(skip_all, fields(user.email_digest = %email_digest(email)))]
     pub async fn get_per_email_model(&self, email: &str) -> ServiceResult<UserModel> {
         let user = UserEntity::find_by_email(email)
             .one(self.db)
+            .instrument(db_span("SELECT", "user"))
             .await
-            .map_err(|err| {
-                ServiceError::internal("Failed to fetch users").with_details(err.to_string())
-            })?;
+            .map_err(|err| ServiceError::from_db(err, "Failed to fetch users"))?;
 
         user.ok_or_else(|| {
             ServiceError::not_found(format!("The user with email {} not found", email))
+                .with_code(ErrorCode::UserNotFound)
         })
     }
+
+    #[instrument(skip_all, fields(user.email_digest = %email_digest(email)))]
+    pub async fn is_email_taken(&self, email: &str) -> ServiceResult<bool> {
+        UserEntity::find_by_email(email)
+            .count(self.db)
+            .instrument(db_span("SELECT", "user"))
+            .await
+            .map(|count| count > 0)
+            .map_err(|err| ServiceError::from_db(err, "Failed to fetch users"))
+    }
+
+    #[instrument(skip(self))]
+    pub async fn get_roles(&self, user_id: i32) -> ServiceResult<Vec<RoleDto>> {
+        let roles = RoleEntity::find()
+            .filter(RoleColumn::UserId.eq(user_id))
+            .order_by_asc(RoleColumn::Name)
+            .all(self.db)
+            .instrument(db_span("SELECT", "role"))
+            .await
+            .map_err(|e| ServiceError::from_db(e, "Failed to fetch roles"))?;
+
+        Ok(roles.into_iter().map(RoleDto::from).collect())
+    }
+
+    /// Holders of `ADMIN_ROLE` or `SUPER_ADMIN_ROLE`.
+    #[instrument(skip(self))]
+    pub async fn is_admin(&self, user_id: i32) -> ServiceResult<bool> {
+        let count = RoleEntity::find()
+            .filter(RoleColumn::UserId.eq(user_id))
+            .filter(RoleColumn::Name.is_in(ADMIN_ROLES))
+            .count(self.db)
+            .instrument(db_span("SELECT", "role"))
+            .await?;
+        Ok(count > 0)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn has_role(&self, user_id: i32, role: &str) -> ServiceResult<bool> {
+        let count = RoleEntity::find()
+            .filter(RoleColumn::UserId.eq(user_id))
+            .filter(RoleColumn::Name.eq(role))
+            .count(self.db)
+            .instrument(db_span("SELECT", "role"))
+            .await?;
+        Ok(count > 0)
+    }
+
+    #[instrument(skip(self, payload, ctx), fields(role = %payload.name))]
+    pub async fn grant_role(
+        &self,
+        user_id: i32,
+        payload: RolePayload,
+        ctx: &AuditContext,
+    ) -> ServiceResult<RoleDto> {
+        let target = format!("user:{}/role:{}", user_id, payload.name);
+        let result = self.insert_role(user_id, payload).await;
+
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::RoleGrant,
+                Some(target),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    async fn insert_role(&self, user_id: i32, payload: RolePayload) -> ServiceResult<RoleDto> {
+        self.get_one(user_id).await?;
+
+        if self.has_role(user_id, &payload.name).await? {
+            return Err(ServiceError::conflict(format!(
+                "The user {} already has the role '{}'",
+                user_id, payload.name
+            ))
+            .with_code(ErrorCode::RoleAlreadyGranted));
+        }
+
+        let role = RoleActiveModel {
+            name: ActiveValue::Set(payload.name),
+            description: ActiveValue::Set(payload.description),
+            user_id: ActiveValue::Set(user_id),
+            ..Default::default()
+        };
+
+        role.insert(self.db)
+            .instrument(db_span("INSERT", "role"))
+            .await
+            .map(RoleDto::from)
+            .map_err(|e| ServiceError::from_db(e, "Failed to grant role"))
+    }
+
+    #[instrument(skip(self, ctx))]
+    pub async fn revoke_role(
+        &self,
+        user_id: i32,
+        role: &str,
+        ctx: &AuditContext,
+    ) -> ServiceResult<()> {
+        let result = self.delete_role(user_id, role).await;
+
+        AuditService::new(self.db)
+            .record(
+                ctx,
+                AuditAction::RoleRevoke,
+                Some(format!("user:{}/role:{}", user_id, role)),
+                AuditResult::from(&result),
+            )
+            .await;
+        result
+    }
+
+    async fn delete_role(&self, user_id: i32, role: &str) -> ServiceResult<()> {
+        let result = RoleEntity::delete_many()
+            .filter(RoleColumn::UserId.eq(user_id))
+            .filter(RoleColumn::Name.eq(role))
+            .exec(self.db)
+            .instrument(db_span("DELETE", "role"))
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(ServiceError::not_found(format!(
+                "The user {} has no role '{}'",
+                user_id, role
+            ))
+            .with_code(ErrorCode::RoleNotFound));
+        }
+        Ok(())
+    }
 }
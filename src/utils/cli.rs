@@ -0,0 +1,117 @@
+use clap::{Args, Parser, Subcommand};
+
+use crate::utils::cfg::ConfigArgs;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "axum-server-poc API server")]
+pub struct Cli {
+    #[command(flatten)]
+    pub config: ConfigArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Default, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server (default)
+    #[default]
+    Serve,
+    /// Apply pending migrations and exit
+    Migrate(MigrateArgs),
+    /// Manage user accounts
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Grant or revoke roles
+    #[command(subcommand)]
+    Role(RoleCommand),
+    /// Manage user sessions
+    #[command(subcommand)]
+    Session(SessionCommand),
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Debug, Args)]
+pub struct MigrateArgs {
+    #[command(subcommand)]
+    pub action: Option<MigrateAction>,
+}
+
+#[derive(Debug, Default, Subcommand)]
+pub enum MigrateAction {
+    /// Apply every pending migration (default)
+    #[default]
+    Up,
+    /// List applied and pending migrations
+    Status,
+    /// Roll back the last migrations
+    Down {
+        #[arg(long, default_value_t = 1)]
+        steps: u32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UserCommand {
+    /// Create a user, the password is read from stdin when `--password` is omitted
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        password: Option<String>,
+        /// Also grant the admin role
+        #[arg(long)]
+        admin: bool,
+    },
+    /// List every user with its roles
+    List,
+    /// Block the login of a user and revoke its sessions
+    Disable {
+        /// User id or email
+        user: String,
+    },
+    /// Set a new password and revoke the user sessions
+    ResetPassword {
+        /// User id or email
+        user: String,
+        #[arg(long)]
+        password: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RoleCommand {
+    /// Grant a role to a user
+    Grant {
+        /// User id or email
+        user: String,
+        role: String,
+        #[arg(long)]
+        description: Option<String>,
+    },
+    /// Revoke a role from a user
+    Revoke {
+        /// User id or email
+        user: String,
+        role: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SessionCommand {
+    /// Revoke every active session of a user
+    Revoke {
+        /// User id or email
+        #[arg(long)]
+        user: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Validate the configuration and print the effective values, secrets masked
+    Check,
+}
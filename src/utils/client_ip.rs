@@ -0,0 +1,29 @@
+use std::net::SocketAddr;
+
+use axum::extract::ConnectInfo;
+use axum::http::request::Parts;
+
+use crate::utils::cfg::RateLimitConfig;
+
+pub const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Address of the client: the first `X-Forwarded-For` hop when the proxy in front is trusted
+/// to overwrite it (`rate_limit.trust_forwarded_for`), else the peer of the connection.
+/// Any client can forge the header, so it is ignored otherwise.
+pub fn client_ip(parts: &Parts, config: &RateLimitConfig) -> Option<String> {
+    let forwarded_ip = config
+        .trust_forwarded_for
+        .then(|| parts.headers.get(X_FORWARDED_FOR))
+        .flatten()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|ip| ip.trim().to_owned())
+        .filter(|ip| !ip.is_empty());
+
+    forwarded_ip.or_else(|| {
+        parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+    })
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use log::LevelFilter;
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr, RuntimeErr};
+use tracing::{info, warn};
+
+use crate::utils::cfg::DatabaseConfig;
+
+pub fn connect_options(config: &DatabaseConfig, url: &str) -> ConnectOptions {
+    let mut options = ConnectOptions::new(url);
+    options
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_secs))
+        .idle_timeout(config.idle_timeout())
+        .max_lifetime(config.max_lifetime())
+        .sqlx_logging(config.log_statements != LevelFilter::Off)
+        .sqlx_logging_level(config.log_statements);
+
+    if config.slow_statement_threshold_ms > 0 {
+        options.sqlx_slow_statements_logging_settings(
+            LevelFilter::Warn,
+            Duration::from_millis(config.slow_statement_threshold_ms),
+        );
+    }
+    options
+}
+
+/// Connects with exponential backoff, so the server survives starting before Postgres
+/// (e.g. `docker compose up`). Gives up after `connect_attempts` attempts.
+pub async fn connect_with_retry(
+    config: &DatabaseConfig,
+    url: &str,
+    name: &str,
+) -> Result<DatabaseConnection, DbErr> {
+    let connect_timeout = Duration::from_secs(config.connect_timeout_secs);
+    let mut delay = Duration::from_millis(config.connect_backoff_ms);
+    let max_delay = Duration::from_millis(config.connect_backoff_max_ms);
+    let mut attempt = 1;
+
+    loop {
+        info!(
+            "Connecting to the {} database (attempt {})...",
+            name, attempt
+        );
+        // The pool keeps retrying until `acquire_timeout`, bound each attempt instead.
+        let result = tokio::time::timeout(
+            connect_timeout,
+            Database::connect(connect_options(config, url)),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            Err(DbErr::Conn(RuntimeErr::Internal(format!(
+                "no connection after {:?}",
+                connect_timeout
+            ))))
+        });
+
+        match result {
+            Ok(connection) => return Ok(connection),
+            Err(err) if attempt < config.connect_attempts => {
+                warn!(
+                    "The {} database is unreachable ({}), retrying in {:?}",
+                    name, err, delay
+                );
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(max_delay);
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
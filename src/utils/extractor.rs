@@ -1,14 +1,254 @@
+use aide::generate::GenContext;
+use aide::openapi::{Operation, ReferenceOr, Response};
+use aide::{OperationInput, OperationOutput};
 use axum::{
-    extract::{FromRequest, Json},
-    http::{Request, StatusCode},
+    body::Bytes,
+    extract::{
+        Form, FromRequest, FromRequestParts, Json, Path, Query,
+        rejection::{BytesRejection, FormRejection, JsonRejection, PathRejection, QueryRejection},
+    },
+    http::{Request, StatusCode, header::CONTENT_TYPE, request::Parts},
 };
+use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 
+use crate::modules::errors::ErrorCode;
 use crate::modules::responses::ApiError;
+use crate::modules::states::AppState;
+use crate::utils::negotiation::{
+    APPLICATION_CBOR, APPLICATION_JSON, APPLICATION_MSGPACK, MediaFormat, add_binary_media_types,
+};
+
+/// `Path` answering with an `ApiError` when a segment does not parse.
+pub struct ExtractPath<T>(pub T);
+
+/// `Query` answering with an `ApiError` when the query string does not parse.
+pub struct ExtractQuery<T>(pub T);
+
+/// `Json` answering with an `ApiError` for a missing content type, a malformed or too large body.
+pub struct ExtractJson<T>(pub T);
+
+/// `Form` answering with an `ApiError`, like `ExtractJson`.
+pub struct ExtractForm<T>(pub T);
+
+/// `ExtractJson` also reading MessagePack and CBOR bodies, picked from `content-type`.
+pub struct ExtractBody<T>(pub T);
 
+/// `ExtractBody` whose payload is also validated.
 pub struct ExtractValidated<T>(pub T);
 
+/// `ExtractQuery` whose parameters are also validated.
+pub struct ExtractValidatedQuery<T>(pub T);
+
+/// `ExtractPath` whose segments are also validated.
+pub struct ExtractValidatedPath<T>(pub T);
+
+/// `ExtractForm` whose fields are also validated.
+pub struct ExtractValidatedForm<T>(pub T);
+
+/// `ExtractValidated` followed by the `AsyncValidate` checks of the payload.
+pub struct ExtractAsyncValidated<T>(pub T);
+
+/// Validation needing the application state, such as a uniqueness check against the
+/// database. Only runs once the payload passed `Validate`.
+pub trait AsyncValidate {
+    /// Field errors are returned as `ValidationErrors` converted into an `ApiError`.
+    fn validate_async(&self, state: &AppState)
+    -> impl Future<Output = Result<(), ApiError>> + Send;
+}
+
+/// Same as `ApiError::from(ValidationErrors)`, naming the part of the request at fault.
+fn invalid(errors: ValidationErrors, detail: &str) -> ApiError {
+    let mut api_error = ApiError::from(errors);
+    api_error.detail = detail.to_owned();
+    api_error
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        // Anything but a client error is a route declared with the wrong path type.
+        match rejection.status() {
+            StatusCode::BAD_REQUEST => ApiError::new(
+                StatusCode::BAD_REQUEST,
+                ErrorCode::InvalidPathParams,
+                rejection.body_text(),
+            ),
+            status => ApiError::new(status, ErrorCode::from_status(status), "Invalid route")
+                .with_details(rejection.body_text()),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            ErrorCode::InvalidQuery,
+            rejection.body_text(),
+        )
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => ApiError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorCode::PayloadTooLarge,
+                "The request body is too large",
+            ),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ErrorCode::UnsupportedMediaType,
+                "Expected a `content-type: application/json` body",
+            ),
+            _ => ApiError::new(
+                StatusCode::BAD_REQUEST,
+                ErrorCode::MalformedBody,
+                rejection.body_text(),
+            ),
+        }
+    }
+}
+
+impl From<BytesRejection> for ApiError {
+    fn from(rejection: BytesRejection) -> Self {
+        match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => ApiError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorCode::PayloadTooLarge,
+                "The request body is too large",
+            ),
+            _ => ApiError::new(
+                StatusCode::BAD_REQUEST,
+                ErrorCode::MalformedBody,
+                rejection.body_text(),
+            ),
+        }
+    }
+}
+
+impl From<FormRejection> for ApiError {
+    fn from(rejection: FormRejection) -> Self {
+        match rejection.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => ApiError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ErrorCode::PayloadTooLarge,
+                "The request body is too large",
+            ),
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ErrorCode::UnsupportedMediaType,
+                "Expected a `content-type: application/x-www-form-urlencoded` body",
+            ),
+            _ => ApiError::new(
+                StatusCode::BAD_REQUEST,
+                ErrorCode::MalformedBody,
+                rejection.body_text(),
+            ),
+        }
+    }
+}
+
+impl<T, S> FromRequestParts<S> for ExtractPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(value) = Path::<T>::from_request_parts(parts, state).await?;
+        Ok(ExtractPath(value))
+    }
+}
+
+impl<T, S> FromRequestParts<S> for ExtractQuery<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state).await?;
+        Ok(ExtractQuery(value))
+    }
+}
+
+impl<T, S> FromRequest<S> for ExtractJson<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(
+        req: Request<axum::body::Body>,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Json(payload) = Json::<T>::from_request(req, state).await?;
+        Ok(ExtractJson(payload))
+    }
+}
+
+impl<T, S> FromRequest<S> for ExtractBody<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(
+        req: Request<axum::body::Body>,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let format = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(MediaFormat::from_content_type);
+
+        match format {
+            Some(MediaFormat::Json) => {
+                let ExtractJson(payload) = ExtractJson::<T>::from_request(req, state).await?;
+                Ok(ExtractBody(payload))
+            }
+            Some(format) => {
+                let bytes = Bytes::from_request(req, state).await?;
+                format.decode(&bytes).map(ExtractBody).map_err(|detail| {
+                    ApiError::new(StatusCode::BAD_REQUEST, ErrorCode::MalformedBody, detail)
+                })
+            }
+            None => Err(ApiError::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                ErrorCode::UnsupportedMediaType,
+                format!(
+                    "Expected a `content-type: {}`, `{}` or `{}` body",
+                    APPLICATION_JSON, APPLICATION_MSGPACK, APPLICATION_CBOR
+                ),
+            )),
+        }
+    }
+}
+
+impl<T, S> FromRequest<S> for ExtractForm<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(
+        req: Request<axum::body::Body>,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let Form(payload) = Form::<T>::from_request(req, state).await?;
+        Ok(ExtractForm(payload))
+    }
+}
+
 impl<T, S> FromRequest<S> for ExtractValidated<T>
 where
     T: Validate + DeserializeOwned + Send,
@@ -16,13 +256,306 @@ where
 {
     type Rejection = ApiError;
 
-    async fn from_request(req: Request<axum::body::Body>, state: &S) -> Result<Self, Self::Rejection> {
-        let Json(payload) = Json::<T>::from_request(req, state)
-            .await
-            .map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, err.to_string(), None))?;
+    async fn from_request(
+        req: Request<axum::body::Body>,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let ExtractBody(payload) = ExtractBody::<T>::from_request(req, state).await?;
 
         payload.validate()?;
 
         Ok(ExtractValidated(payload))
     }
 }
+
+impl<T, S> FromRequestParts<S> for ExtractValidatedQuery<T>
+where
+    T: Validate + DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ExtractQuery(query) = ExtractQuery::<T>::from_request_parts(parts, state).await?;
+
+        query
+            .validate()
+            .map_err(|errors| invalid(errors, "The query string is invalid"))?;
+
+        Ok(ExtractValidatedQuery(query))
+    }
+}
+
+impl<T, S> FromRequestParts<S> for ExtractValidatedPath<T>
+where
+    T: Validate + DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ExtractPath(path) = ExtractPath::<T>::from_request_parts(parts, state).await?;
+
+        path.validate()
+            .map_err(|errors| invalid(errors, "The path parameters are invalid"))?;
+
+        Ok(ExtractValidatedPath(path))
+    }
+}
+
+impl<T, S> FromRequest<S> for ExtractValidatedForm<T>
+where
+    T: Validate + DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(
+        req: Request<axum::body::Body>,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let ExtractForm(payload) = ExtractForm::<T>::from_request(req, state).await?;
+
+        payload
+            .validate()
+            .map_err(|errors| invalid(errors, "The form is invalid"))?;
+
+        Ok(ExtractValidatedForm(payload))
+    }
+}
+
+impl<T> FromRequest<AppState> for ExtractAsyncValidated<T>
+where
+    T: Validate + AsyncValidate + DeserializeOwned + Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(
+        req: Request<axum::body::Body>,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let ExtractValidated(payload) = ExtractValidated::<T>::from_request(req, state).await?;
+
+        payload.validate_async(state).await?;
+
+        Ok(ExtractAsyncValidated(payload))
+    }
+}
+
+/// The problem document under each of `statuses`, for `OperationInput::inferred_early_responses`.
+pub fn problem_responses(
+    ctx: &mut GenContext,
+    operation: &mut Operation,
+    statuses: &[u16],
+) -> Vec<(Option<u16>, Response)> {
+    statuses
+        .iter()
+        .filter_map(|status| {
+            ApiError::operation_response(ctx, operation).map(|response| (Some(*status), response))
+        })
+        .collect()
+}
+
+impl<T: JsonSchema> OperationInput for ExtractPath<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        Path::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        problem_responses(ctx, operation, &[400])
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractQuery<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        Query::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        problem_responses(ctx, operation, &[400])
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractJson<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        Json::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        problem_responses(ctx, operation, &[400, 413, 415])
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractBody<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        Json::<T>::operation_input(ctx, operation);
+        if let Some(ReferenceOr::Item(body)) = &mut operation.request_body {
+            add_binary_media_types(&mut body.content);
+        }
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        ExtractJson::<T>::inferred_early_responses(ctx, operation)
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractValidated<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        ExtractBody::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        ExtractBody::<T>::inferred_early_responses(ctx, operation)
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractForm<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        Form::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        problem_responses(ctx, operation, &[400, 413, 415])
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractValidatedQuery<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        ExtractQuery::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        ExtractQuery::<T>::inferred_early_responses(ctx, operation)
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractValidatedPath<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        ExtractPath::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        ExtractPath::<T>::inferred_early_responses(ctx, operation)
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractValidatedForm<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        ExtractForm::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        ExtractForm::<T>::inferred_early_responses(ctx, operation)
+    }
+}
+
+impl<T: JsonSchema> OperationInput for ExtractAsyncValidated<T> {
+    fn operation_input(ctx: &mut GenContext, operation: &mut Operation) {
+        ExtractBody::<T>::operation_input(ctx, operation);
+    }
+
+    fn inferred_early_responses(
+        ctx: &mut GenContext,
+        operation: &mut Operation,
+    ) -> Vec<(Option<u16>, Response)> {
+        ExtractBody::<T>::inferred_early_responses(ctx, operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::extract::FromRequest;
+    use axum::http::Request;
+    use axum::http::header::CONTENT_TYPE;
+    use serde::Deserialize;
+    use validator::Validate;
+
+    use super::{ExtractValidated, ExtractValidatedForm};
+    use crate::modules::errors::ErrorCode;
+
+    #[derive(Deserialize, Validate)]
+    struct Address {
+        #[validate(length(min = 1))]
+        city: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Contact {
+        #[validate(nested)]
+        address: Address,
+        #[validate(nested)]
+        previous_addresses: Vec<Address>,
+    }
+
+    #[tokio::test]
+    async fn nested_errors_are_keyed_by_their_path() {
+        let request = Request::post("/")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                r#"{"address":{"city":""},"previous_addresses":[{"city":"Lyon"},{"city":""}]}"#,
+            ))
+            .unwrap();
+
+        let error = match ExtractValidated::<Contact>::from_request(request, &()).await {
+            Ok(_) => panic!("the payload must be rejected"),
+            Err(error) => error,
+        };
+
+        assert_eq!(error.code, ErrorCode::ValidationFailed);
+        let fields: Vec<_> = error.errors.unwrap().into_keys().collect();
+        assert_eq!(fields, ["address.city", "previous_addresses[1].city"]);
+    }
+
+    #[tokio::test]
+    async fn forms_are_validated_like_json_bodies() {
+        let form = |body: &'static str| {
+            Request::post("/")
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let ExtractValidatedForm(address) =
+            match ExtractValidatedForm::<Address>::from_request(form("city=Lyon"), &()).await {
+                Ok(address) => address,
+                Err(error) => panic!("the form must be accepted: {}", error.detail),
+            };
+        assert_eq!(address.city, "Lyon");
+
+        let error = match ExtractValidatedForm::<Address>::from_request(form("city="), &()).await {
+            Ok(_) => panic!("the form must be rejected"),
+            Err(error) => error,
+        };
+        assert_eq!(error.code, ErrorCode::ValidationFailed);
+        let fields: Vec<_> = error.errors.unwrap().into_keys().collect();
+        assert_eq!(fields, ["city"]);
+    }
+}
@@ -0,0 +1,337 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use chrono::{SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use sha2::Sha256;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber, warn};
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+/// Swaps the active `EnvFilter` without restarting the process.
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Any field whose name contains one of these is never written to the logs.
+const SENSITIVE_FIELDS: [&str; 5] = ["password", "token", "authorization", "secret", "cookie"];
+
+pub fn is_sensitive(field: &str) -> bool {
+    let field = field.to_lowercase();
+    SENSITIVE_FIELDS
+        .iter()
+        .any(|sensitive| field.contains(sensitive))
+}
+
+/// Key of `email_digest`, drawn at random unless `log.email_digest_secret` is set.
+static EMAIL_DIGEST_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Keys `email_digest` with `secret`, shared by the replicas whose digests must match.
+/// Without one, digests only correlate the events of the current process.
+pub fn init_email_digest(secret: Option<&str>) {
+    let key = secret.map_or_else(random_key, |secret| secret.as_bytes().to_vec());
+    if EMAIL_DIGEST_KEY.set(key).is_err() {
+        warn!("The email digest key was already set, the configured one is ignored");
+    }
+}
+
+fn random_key() -> Vec<u8> {
+    rand::random::<[u8; 32]>().to_vec()
+}
+
+/// Stands for an email in spans: the events of one address can be correlated without
+/// exporting the address itself. Keyed, so that it cannot be reversed from a wordlist.
+pub fn email_digest(email: &str) -> String {
+    let key = EMAIL_DIGEST_KEY.get_or_init(random_key);
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(email.to_lowercase().as_bytes());
+    let digest = format!("{:x}", mac.finalize().into_bytes());
+    digest[..16].to_owned()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[serde(alias = "pretty")]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "text" | "pretty" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "Unknown log format '{}', expected text or json",
+                other
+            )),
+        }
+    }
+}
+
+/// Collects the fields of an event or span, masking sensitive ones.
+#[derive(Default)]
+struct RedactingVisitor {
+    message: Option<String>,
+    fields: Map<String, Value>,
+}
+
+impl RedactingVisitor {
+    fn collect<R: RecordFields>(fields: R) -> Self {
+        let mut visitor = Self::default();
+        fields.record(&mut visitor);
+        visitor
+    }
+
+    fn insert(&mut self, field: &Field, value: Value) {
+        let value = if is_sensitive(field.name()) {
+            Value::from(REDACTED)
+        } else {
+            value
+        };
+        match (field.name(), value) {
+            ("message", Value::String(message)) => self.message = Some(message),
+            (name, value) => {
+                self.fields.insert(name.to_owned(), value);
+            }
+        }
+    }
+
+    fn into_json(mut self) -> Map<String, Value> {
+        if let Some(message) = self.message {
+            self.fields
+                .insert("message".to_owned(), Value::from(message));
+        }
+        self.fields
+    }
+}
+
+impl Visit for RedactingVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, Value::from(format!("{:?}", value)));
+    }
+}
+
+/// `message key=value ...` fields for the human readable format.
+pub struct RedactedFields;
+
+impl<'writer> FormatFields<'writer> for RedactedFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let visitor = RedactingVisitor::collect(fields);
+        let mut separator = "";
+        if let Some(message) = &visitor.message {
+            writer.write_str(message)?;
+            separator = " ";
+        }
+        for (name, value) in &visitor.fields {
+            match value {
+                Value::String(value) => write!(writer, "{}{}={}", separator, name, value)?,
+                value => write!(writer, "{}{}={}", separator, name, value)?,
+            }
+            separator = " ";
+        }
+        Ok(())
+    }
+}
+
+/// Span fields stored as a JSON object, so `JsonFormat` can embed them as is.
+pub struct RedactedJsonFields;
+
+impl<'writer> FormatFields<'writer> for RedactedJsonFields {
+    fn format_fields<R: RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let fields = RedactingVisitor::collect(fields).into_json();
+        write!(writer, "{}", Value::Object(fields))
+    }
+
+    fn add_fields(
+        &self,
+        current: &mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut merged: Map<String, Value> =
+            serde_json::from_str(&current.fields).unwrap_or_default();
+        merged.extend(RedactingVisitor::collect(fields).into_json());
+        current.fields = Value::Object(merged).to_string();
+        Ok(())
+    }
+}
+
+/// One JSON document per line: timestamp, level, target, fields and the span stack.
+pub struct JsonFormat;
+
+impl<S> FormatEvent<S, RedactedJsonFields> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, RedactedJsonFields>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let spans: Vec<Value> = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let mut fields: Map<String, Value> = span
+                    .extensions()
+                    .get::<FormattedFields<RedactedJsonFields>>()
+                    .and_then(|fields| serde_json::from_str(&fields.fields).ok())
+                    .unwrap_or_default();
+                fields.insert("name".to_owned(), Value::from(span.name()));
+                Value::Object(fields)
+            })
+            .collect();
+
+        let line = json!({
+            "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "fields": RedactingVisitor::collect(event).into_json(),
+            "spans": spans,
+        });
+        writeln!(writer, "{}", line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use tracing_subscriber::fmt::MakeWriter;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use sha2::{Digest, Sha256};
+
+    use super::{JsonFormat, REDACTED, RedactedFields, RedactedJsonFields, email_digest};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    impl Buffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    fn log_sensitive_event() {
+        let span = tracing::info_span!("login", user.email = "jane@example.com", token = "abc");
+        let _guard = span.enter();
+        tracing::info!(
+            password = "hunter2",
+            authorization = "Bearer abc",
+            user_id = 42,
+            "logged in"
+        );
+    }
+
+    #[test]
+    fn text_format_masks_sensitive_fields() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .fmt_fields(RedactedFields)
+                .with_ansi(false)
+                .with_writer(buffer.clone()),
+        );
+        tracing::subscriber::with_default(subscriber, log_sensitive_event);
+
+        let logs = buffer.contents();
+        assert!(logs.contains("logged in"));
+        assert!(logs.contains("user_id=42"));
+        assert!(logs.contains(&format!("password={}", REDACTED)));
+        assert!(!logs.contains("hunter2"));
+        assert!(!logs.contains("Bearer abc"));
+    }
+
+    #[test]
+    fn json_format_masks_sensitive_fields() {
+        let buffer = Buffer::default();
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .fmt_fields(RedactedJsonFields)
+                .event_format(JsonFormat)
+                .with_writer(buffer.clone()),
+        );
+        tracing::subscriber::with_default(subscriber, log_sensitive_event);
+
+        let line: serde_json::Value = serde_json::from_str(buffer.contents().trim()).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["fields"]["message"], "logged in");
+        assert_eq!(line["fields"]["user_id"], 42);
+        assert_eq!(line["fields"]["password"], REDACTED);
+        assert_eq!(line["fields"]["authorization"], REDACTED);
+        assert_eq!(line["spans"][0]["name"], "login");
+        assert_eq!(line["spans"][0]["user.email"], "jane@example.com");
+        assert_eq!(line["spans"][0]["token"], REDACTED);
+    }
+
+    #[test]
+    fn email_digests_are_keyed() {
+        let digest = email_digest("jane@example.com");
+        assert_eq!(digest.len(), 16);
+        assert_eq!(email_digest("Jane@Example.com"), digest);
+
+        let unkeyed = format!("{:x}", Sha256::digest(b"jane@example.com"));
+        assert_ne!(digest, unkeyed[..16]);
+    }
+}
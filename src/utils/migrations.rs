@@ -0,0 +1,136 @@
+use std::fmt;
+use std::str::FromStr;
+
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Transaction scoped advisory lock shared by every instance, so that replicas booting
+/// together apply migrations one after the other. The key is arbitrary but must not change.
+const MIGRATION_LOCK_SQL: &str = "SELECT pg_advisory_xact_lock(7358240116902381665)";
+
+/// What the server does with pending migrations at startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationMode {
+    /// Apply them, under the advisory lock.
+    #[default]
+    Auto,
+    /// Refuse to start while some are pending, they are applied with `migrate`.
+    Check,
+    /// Do not look at them.
+    Off,
+}
+
+impl FromStr for MigrationMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "check" => Ok(Self::Check),
+            "off" => Ok(Self::Off),
+            other => Err(format!(
+                "Unknown migration mode '{}', expected auto, check or off",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for MigrationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Auto => "auto",
+            Self::Check => "check",
+            Self::Off => "off",
+        })
+    }
+}
+
+pub async fn apply_migration_mode(
+    db: &DatabaseConnection,
+    mode: MigrationMode,
+) -> Result<(), DbErr> {
+    match mode {
+        MigrationMode::Auto => run_migrations(db).await,
+        MigrationMode::Check => {
+            let pending = pending_migrations(db).await?;
+            if pending.is_empty() {
+                return Ok(());
+            }
+            Err(DbErr::Migration(format!(
+                "{} pending migration(s): {}. Apply them with `axum-server-poc migrate`",
+                pending.len(),
+                pending.join(", ")
+            )))
+        }
+        MigrationMode::Off => {
+            info!("Migrations are not checked at startup");
+            Ok(())
+        }
+    }
+}
+
+/// Migrations of this binary and of the database that the other one lacks.
+pub struct MigrationState {
+    /// Known to this binary but not applied yet.
+    pub pending: Vec<String>,
+    /// Applied but unknown to this binary, such as those of a newer release after a rollback.
+    pub unknown: Vec<String>,
+}
+
+/// Unlike `Migrator::get_pending_migrations`, does not fail on unknown applied migrations.
+pub async fn migration_state(db: &DatabaseConnection) -> Result<MigrationState, DbErr> {
+    let applied: Vec<String> = Migrator::get_migration_models(db)
+        .await?
+        .into_iter()
+        .map(|model| model.version)
+        .collect();
+    let known: Vec<String> = Migrator::migrations()
+        .iter()
+        .map(|migration| migration.name().to_owned())
+        .collect();
+
+    Ok(MigrationState {
+        pending: known
+            .iter()
+            .filter(|name| !applied.contains(name))
+            .cloned()
+            .collect(),
+        unknown: applied
+            .into_iter()
+            .filter(|name| !known.contains(name))
+            .collect(),
+    })
+}
+
+pub async fn pending_migrations(db: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+    Ok(migration_state(db).await?.pending)
+}
+
+/// Applies every pending migration in a single transaction holding the advisory lock.
+pub async fn run_migrations(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let transaction = db.begin().await?;
+    info!("Waiting for the migration lock...");
+    transaction.execute_unprepared(MIGRATION_LOCK_SQL).await?;
+
+    let pending = Migrator::get_pending_migrations(&transaction).await?;
+    if pending.is_empty() {
+        info!("The database schema is up to date");
+    } else {
+        info!("Applying {} migration(s)...", pending.len());
+        Migrator::up(&transaction, None).await?;
+    }
+    transaction.commit().await
+}
+
+/// Rolls back the last `steps` migrations, under the same lock.
+pub async fn rollback_migrations(db: &DatabaseConnection, steps: u32) -> Result<(), DbErr> {
+    let transaction = db.begin().await?;
+    transaction.execute_unprepared(MIGRATION_LOCK_SQL).await?;
+    info!("Rolling back {} migration(s)...", steps);
+    Migrator::down(&transaction, Some(steps)).await?;
+    transaction.commit().await
+}
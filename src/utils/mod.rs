@@ -1,2 +1,11 @@
 pub mod cfg;
+pub mod cli;
+pub mod client_ip;
+pub mod db;
 pub mod extractor;
+pub mod logging;
+pub mod migrations;
+pub mod negotiation;
+pub mod request_id;
+pub mod shutdown;
+pub mod telemetry;
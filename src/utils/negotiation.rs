@@ -0,0 +1,210 @@
+use aide::openapi::MediaType;
+use axum::extract::Request;
+use axum::http::header::ACCEPT;
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::modules::errors::ErrorCode;
+use crate::modules::responses::ApiError;
+
+pub const APPLICATION_JSON: &str = "application/json";
+pub const APPLICATION_MSGPACK: &str = "application/msgpack";
+pub const APPLICATION_CBOR: &str = "application/cbor";
+
+/// Body formats the API reads and writes, JSON being the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MediaFormat {
+    #[default]
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl MediaFormat {
+    pub fn mime(self) -> &'static str {
+        match self {
+            Self::Json => APPLICATION_JSON,
+            Self::MessagePack => APPLICATION_MSGPACK,
+            Self::Cbor => APPLICATION_CBOR,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::MessagePack => "MessagePack",
+            Self::Cbor => "CBOR",
+        }
+    }
+
+    /// Format of a `content-type` value, `application/*+json` counting as JSON like `Json` does.
+    pub fn from_content_type(value: &str) -> Option<Self> {
+        let essence = value.split(';').next()?.trim().to_ascii_lowercase();
+        match essence.as_str() {
+            APPLICATION_MSGPACK | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Self::MessagePack)
+            }
+            APPLICATION_CBOR => Some(Self::Cbor),
+            APPLICATION_JSON => Some(Self::Json),
+            essence if essence.starts_with("application/") && essence.ends_with("+json") => {
+                Some(Self::Json)
+            }
+            _ => None,
+        }
+    }
+
+    /// Preferred format of an `accept` header: highest `q` first, then exact types over
+    /// wildcards, then header order. `None` when the client accepts none of them.
+    pub fn from_accept(headers: &HeaderMap) -> Option<Self> {
+        let ranges: Vec<&str> = headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+            .collect();
+        if ranges.is_empty() {
+            return Some(Self::Json);
+        }
+
+        let mut best: Option<(f32, bool, Self)> = None;
+        for range in ranges {
+            let mut params = range.split(';');
+            let essence = params
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+            let quality = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|quality| quality.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let (exact, format) = match essence.as_str() {
+                "*/*" | "application/*" => (false, Self::Json),
+                essence => match Self::from_content_type(essence) {
+                    Some(format) => (true, format),
+                    None => continue,
+                },
+            };
+            if quality <= 0.0 {
+                continue;
+            }
+            if best.is_none_or(|(best_quality, best_exact, _)| {
+                quality > best_quality || (quality == best_quality && exact && !best_exact)
+            }) {
+                best = Some((quality, exact, format));
+            }
+        }
+        best.map(|(_, _, format)| format)
+    }
+
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Json => serde_json::to_vec(value).map_err(|err| err.to_string()),
+            // Named fields keep the document shaped like its JSON counterpart.
+            Self::MessagePack => rmp_serde::to_vec_named(value).map_err(|err| err.to_string()),
+            Self::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(value, &mut bytes).map_err(|err| err.to_string())?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, String> {
+        let decoded = match self {
+            Self::Json => serde_json::from_slice(bytes).map_err(|err| err.to_string()),
+            Self::MessagePack => rmp_serde::from_slice(bytes).map_err(|err| err.to_string()),
+            Self::Cbor => ciborium::from_reader(bytes).map_err(|err| err.to_string()),
+        };
+        decoded.map_err(|err| format!("Failed to deserialize the {} body: {}", self.name(), err))
+    }
+}
+
+/// Documents the MessagePack and CBOR variants of the `application/json` entry of `content`.
+pub fn add_binary_media_types(content: &mut IndexMap<String, MediaType>) {
+    if let Some(json) = content.get(APPLICATION_JSON).cloned() {
+        content.insert(APPLICATION_MSGPACK.to_owned(), json.clone());
+        content.insert(APPLICATION_CBOR.to_owned(), json);
+    }
+}
+
+tokio::task_local! {
+    static RESPONSE_FORMAT: MediaFormat;
+}
+
+/// Format negotiated for the request handled by the current task, JSON outside of one.
+pub fn current_response_format() -> MediaFormat {
+    RESPONSE_FORMAT
+        .try_with(|format| *format)
+        .unwrap_or_default()
+}
+
+/// Picks the response format from `accept` before the handler runs, so a request the
+/// client cannot read the answer of is refused with a 406 instead of being executed.
+pub async fn negotiate_format(request: Request, next: Next) -> Response {
+    match MediaFormat::from_accept(request.headers()) {
+        Some(format) => RESPONSE_FORMAT.scope(format, next.run(request)).await,
+        None => ApiError::new(
+            StatusCode::NOT_ACCEPTABLE,
+            ErrorCode::NotAcceptable,
+            format!(
+                "Expected an `accept` header allowing {}, {} or {}",
+                APPLICATION_JSON, APPLICATION_MSGPACK, APPLICATION_CBOR
+            ),
+        )
+        .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderMap;
+    use axum::http::header::ACCEPT;
+
+    use super::MediaFormat;
+
+    fn accept(value: &str) -> Option<MediaFormat> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, value.parse().unwrap());
+        MediaFormat::from_accept(&headers)
+    }
+
+    #[test]
+    fn accept_header_picks_the_preferred_supported_format() {
+        assert_eq!(
+            MediaFormat::from_accept(&HeaderMap::new()),
+            Some(MediaFormat::Json)
+        );
+        assert_eq!(accept("*/*"), Some(MediaFormat::Json));
+        assert_eq!(
+            accept("application/msgpack"),
+            Some(MediaFormat::MessagePack)
+        );
+        assert_eq!(
+            accept("application/json;q=0.5, application/cbor"),
+            Some(MediaFormat::Cbor)
+        );
+        assert_eq!(
+            accept("*/*, application/x-msgpack"),
+            Some(MediaFormat::MessagePack)
+        );
+        assert_eq!(accept("text/html, application/xml;q=0.9"), None);
+        assert_eq!(accept("application/cbor;q=0"), None);
+    }
+
+    #[test]
+    fn binary_formats_round_trip() {
+        let value = serde_json::json!({ "email": "root@example.com", "roles": ["admin"] });
+        for format in [MediaFormat::MessagePack, MediaFormat::Cbor] {
+            let bytes = format.encode(&value).unwrap();
+            let decoded: serde_json::Value = format.decode(&bytes).unwrap();
+            assert_eq!(decoded, value);
+        }
+    }
+}
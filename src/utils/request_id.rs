@@ -0,0 +1,30 @@
+use axum::extract::Request;
+use axum::http::HeaderName;
+use axum::middleware::Next;
+use axum::response::Response;
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Request id of the request being handled by the current task, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// Makes the `x-request-id` set by `SetRequestIdLayer` reachable from code that has no
+/// access to the request, such as `ApiError::into_response`.
+pub async fn scope_request_id(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    match request_id {
+        Some(request_id) => REQUEST_ID.scope(request_id, next.run(request)).await,
+        None => next.run(request).await,
+    }
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{info, warn};
+
+/// Resolves on the first SIGINT (Ctrl+C) or SIGTERM (sent by Docker and Kubernetes).
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                warn!("Cannot listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("SIGINT received"),
+        _ = terminate => info!("SIGTERM received"),
+    }
+}
+
+/// Long running tasks living next to the HTTP server (metrics listener, cleanups...).
+/// Each task receives a token cancelled on shutdown and must return once it fires.
+#[derive(Clone, Default)]
+pub struct BackgroundTasks {
+    token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl BackgroundTasks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn<F, Fut>(&self, task: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.tracker.spawn(task(self.token.child_token()));
+    }
+
+    /// Cancels every task and waits at most `timeout` for them to return.
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.token.cancel();
+        self.tracker.close();
+        if tokio::time::timeout(timeout, self.tracker.wait())
+            .await
+            .is_err()
+        {
+            warn!(
+                "{} background task(s) did not stop within {:?}",
+                self.tracker.len(),
+                timeout
+            );
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use opentelemetry::global;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use tracing::Span;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, reload};
+
+use crate::utils::cfg::Config;
+use crate::utils::logging::{
+    JsonFormat, LogFilterHandle, LogFormat, RedactedFields, RedactedJsonFields, init_email_digest,
+};
+
+/// Builds a tracer provider exporting spans over OTLP/HTTP to `endpoint` (collector base URL).
+pub fn build_tracer_provider(endpoint: &str, service_name: &str) -> Result<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder_empty()
+                .with_service_name(service_name.to_owned())
+                .build(),
+        )
+        .build())
+}
+
+pub struct Tracing {
+    pub log_filter: LogFilterHandle,
+    /// Must be shut down before exit so buffered spans are flushed.
+    pub tracer_provider: Option<SdkTracerProvider>,
+}
+
+/// Installs the global subscriber: a reloadable `EnvFilter`, redacted text or JSON logs
+/// and, when configured, the OTLP exporter.
+pub fn init_tracing(config: &Config) -> Result<Tracing> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    init_email_digest(config.log.email_digest_secret.as_deref());
+
+    let telemetry = &config.telemetry;
+    let provider = telemetry
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| build_tracer_provider(endpoint, &telemetry.service_name))
+        .transpose()?;
+
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(telemetry.service_name.clone()))
+    });
+
+    let (filter_layer, log_filter) = reload::Layer::new(EnvFilter::try_new(&config.log.filter)?);
+    let fmt_layer = match config.log.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .fmt_fields(RedactedFields)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .fmt_fields(RedactedJsonFields)
+            .event_format(JsonFormat)
+            .boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(fmt_layer)
+        .with(otel_layer)
+        .init();
+
+    Ok(Tracing {
+        log_filter,
+        tracer_provider: provider,
+    })
+}
+
+/// Client span wrapping a single database round trip, named after OTel's database conventions.
+pub fn db_span(operation: &'static str, table: &'static str) -> Span {
+    tracing::info_span!(
+        "db.query",
+        otel.name = format!("{} {}", operation, table),
+        otel.kind = "client",
+        db.system = "postgresql",
+        db.operation = operation,
+        db.sql.table = table,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::Router;
+    use axum::body::Bytes;
+    use axum::routing::post;
+    use opentelemetry::trace::{Tracer, TracerProvider};
+    use tokio::sync::mpsc;
+
+    use super::build_tracer_provider;
+
+    /// Stands in for an OTLP collector: records every `/v1/traces` payload it receives.
+    async fn spawn_collector() -> (String, mpsc::UnboundedReceiver<Bytes>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let app = Router::new().route(
+            "/v1/traces",
+            post(move |body: Bytes| async move {
+                sender.send(body).ok();
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (endpoint, receiver)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn exports_spans_to_the_otlp_collector() {
+        let (endpoint, mut received) = spawn_collector().await;
+        let provider = build_tracer_provider(&endpoint, "telemetry-test").unwrap();
+
+        provider
+            .tracer("telemetry-test")
+            .in_span("exported-span", |_| {});
+        let flushed = tokio::task::spawn_blocking(move || provider.force_flush())
+            .await
+            .unwrap();
+        assert!(flushed.is_ok(), "flush failed: {:?}", flushed);
+
+        let payload = tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("the collector received nothing")
+            .unwrap();
+        let needle = b"exported-span";
+        assert!(payload.windows(needle.len()).any(|window| window == needle));
+    }
+}
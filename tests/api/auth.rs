@@ -0,0 +1,171 @@
+use axum::http::{Method, StatusCode};
+use axum_server_poc::modules::models::entities::audit_event::Column as AuditEventColumn;
+use axum_server_poc::modules::models::entities::audit_event::Entity as AuditEventEntity;
+use axum_server_poc::modules::models::entities::session::Entity as SessionEntity;
+use chrono::TimeDelta;
+use sea_orm::{EntityTrait, QueryOrder};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::common::{PASSWORD, TestApp};
+
+#[tokio::test]
+async fn login_returns_a_session_token() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice@example.com", false).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/auth/login",
+            None,
+            Some(json!({ "email": "Alice@Example.com", "password": PASSWORD })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.json()["token"].is_string());
+}
+
+#[tokio::test]
+async fn login_opens_sessions_for_the_configured_ttl() {
+    let app = TestApp::spawn_with(|config| config.session.ttl_hours = 2).await;
+    app.create_user("alice@example.com", false).await;
+
+    let token = app.login("alice@example.com").await;
+    let session = SessionEntity::find_by_id(Uuid::parse_str(&token).unwrap())
+        .one(app.db.as_ref())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(session.expire_at - session.created_at, TimeDelta::hours(2));
+}
+
+#[tokio::test]
+async fn login_rejects_wrong_credentials() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice@example.com", false).await;
+
+    let response = app
+        .request(
+            Method::POST,
+            "/auth/login",
+            None,
+            Some(json!({ "email": "alice@example.com", "password": "not-the-password" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.code(), "AUTH_INVALID_CREDENTIALS");
+
+    // An unknown email is indistinguishable from a wrong password.
+    let response = app
+        .request(
+            Method::POST,
+            "/auth/login",
+            None,
+            Some(json!({ "email": "nobody@example.com", "password": PASSWORD })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.code(), "AUTH_INVALID_CREDENTIALS");
+
+    let response = app
+        .request(
+            Method::POST,
+            "/auth/login",
+            None,
+            Some(json!({ "email": "not-an-email", "password": PASSWORD })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "VALIDATION_FAILED");
+}
+
+#[tokio::test]
+async fn me_returns_the_authenticated_user() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice@example.com", false).await;
+    let token = app.login("alice@example.com").await;
+
+    let response = app
+        .request(Method::POST, "/auth/me", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["id"], alice["id"]);
+
+    let response = app.request(Method::POST, "/auth/me", None, None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.code(), "AUTH_MISSING_TOKEN");
+}
+
+#[tokio::test]
+async fn logout_revokes_the_session() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice@example.com", false).await;
+    let token = app.login("alice@example.com").await;
+
+    let response = app
+        .request(Method::POST, "/auth/logout", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["token"], token.as_str());
+
+    let response = app
+        .request(Method::POST, "/auth/me", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn error_details_follow_the_debug_setting() {
+    let app = TestApp::spawn().await;
+    let response = app
+        .request(Method::POST, "/auth/me", Some("not-a-uuid"), None)
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "SESSION_TOKEN_MALFORMED");
+    assert!(response.json().get("details").is_none());
+
+    let app = TestApp::spawn_with(|config| config.debug = true).await;
+    let response = app
+        .request(Method::POST, "/auth/me", Some("not-a-uuid"), None)
+        .await;
+    assert!(response.json()["details"].is_string());
+}
+
+#[tokio::test]
+async fn logins_are_audited_per_user_without_the_email() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice@example.com", false).await;
+
+    app.request(
+        Method::POST,
+        "/auth/login",
+        None,
+        Some(json!({ "email": "alice@example.com", "password": "not-the-password" })),
+    )
+    .await;
+    app.request(
+        Method::POST,
+        "/auth/login",
+        None,
+        Some(json!({ "email": "nobody@example.com", "password": PASSWORD })),
+    )
+    .await;
+    app.login("alice@example.com").await;
+
+    let targets: Vec<_> = AuditEventEntity::find()
+        .order_by_asc(AuditEventColumn::Id)
+        .all(app.db.as_ref())
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|event| event.action == "auth.login")
+        .map(|event| (event.target.unwrap_or_default(), event.result))
+        .collect();
+    let alice_target = format!("user:{}", alice["id"]);
+    assert_eq!(targets.len(), 3);
+    assert_eq!(targets[0], (alice_target.clone(), "failure".to_owned()));
+    assert!(targets[1].0.starts_with("email:"));
+    assert!(!targets[1].0.contains("nobody"));
+    assert_eq!(targets[2], (alice_target, "success".to_owned()));
+}
@@ -0,0 +1,44 @@
+use axum::http::{Method, StatusCode};
+use axum_server_poc::utils::migrations::MigrationMode;
+use sea_orm::ConnectionTrait;
+
+use crate::common::TestApp;
+
+/// Rolls the schema back by one migration and records one of a newer release, like a
+/// database left behind by a rollback to this binary.
+async fn desynchronize_migrations(app: &TestApp) {
+    app.db
+        .execute_unprepared(
+            "DELETE FROM seaql_migrations WHERE version = \
+             (SELECT max(version) FROM seaql_migrations); \
+             INSERT INTO seaql_migrations (version, applied_at) VALUES ('m29990101_000001_next', 0)",
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn pending_migrations_only_fail_readiness_in_auto_mode() {
+    let app =
+        TestApp::spawn_with(|config| config.database.migration_mode = MigrationMode::Check).await;
+    desynchronize_migrations(&app).await;
+
+    let response = app.request(Method::GET, "/health/ready", None, None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+    let migrations = &response.json()["components"]["migrations"];
+    assert_eq!(migrations["status"], "ok");
+    assert_eq!(
+        migrations["details"],
+        "1 pending migration(s), 1 applied migration(s) unknown to this release"
+    );
+
+    let app = TestApp::spawn().await;
+    desynchronize_migrations(&app).await;
+
+    let response = app.request(Method::GET, "/health/ready", None, None).await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        response.json()["components"]["migrations"]["status"],
+        "fail"
+    );
+}
@@ -0,0 +1,117 @@
+use axum::body::Body;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use axum::http::{Method, Request, StatusCode};
+use axum_server_poc::modules::idempotency::domain::{
+    Claim, IDEMPOTENCY_KEY, IDEMPOTENT_REPLAYED, fingerprint, storage_key,
+};
+use serde_json::{Value, json};
+
+use crate::common::{PASSWORD, TestApp};
+
+fn keyed_post(uri: &str, key: &str, token: Option<&str>, body: &Value) -> Request<Body> {
+    let mut request = Request::post(uri)
+        .header(CONTENT_TYPE, "application/json")
+        .header(IDEMPOTENCY_KEY, key);
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+#[tokio::test]
+async fn create_user_retries_are_replayed() {
+    let app = TestApp::spawn().await;
+    app.create_user("admin@example.com", true).await;
+    let token = app.login("admin@example.com").await;
+    let body = json!({ "name": "alice", "email": "alice@example.com", "password": PASSWORD });
+
+    let first = app
+        .send(keyed_post("/users", "create-alice", Some(&token), &body))
+        .await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.text());
+    assert!(!first.headers.contains_key(IDEMPOTENT_REPLAYED));
+
+    let retry = app
+        .send(keyed_post("/users", "create-alice", Some(&token), &body))
+        .await;
+    assert_eq!(retry.status, StatusCode::OK);
+    assert_eq!(retry.headers[IDEMPOTENT_REPLAYED], "true");
+    assert_eq!(retry.json(), first.json());
+
+    let users = app.request(Method::GET, "/users", None, None).await;
+    let alices = users
+        .json()
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|user| user["email"] == "alice@example.com")
+        .count();
+    assert_eq!(alices, 1);
+
+    let other_body = json!({ "name": "bob", "email": "bob@example.com", "password": PASSWORD });
+    let response = app
+        .send(keyed_post(
+            "/users",
+            "create-alice",
+            Some(&token),
+            &other_body,
+        ))
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.code(), "IDEMPOTENCY_KEY_REUSED");
+}
+
+#[tokio::test]
+async fn login_retries_are_replayed() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice@example.com", false).await;
+    let body = json!({ "email": "alice@example.com", "password": PASSWORD });
+
+    let first = app
+        .send(keyed_post("/auth/login", "login-1", None, &body))
+        .await;
+    assert_eq!(first.status, StatusCode::OK);
+    let retry = app
+        .send(keyed_post("/auth/login", "login-1", None, &body))
+        .await;
+    assert_eq!(retry.headers[IDEMPOTENT_REPLAYED], "true");
+    assert_eq!(retry.json()["token"], first.json()["token"]);
+
+    // Failed logins are stored too, a retry does not try the password again.
+    let wrong = json!({ "email": "alice@example.com", "password": "not-the-password" });
+    let first = app
+        .send(keyed_post("/auth/login", "login-2", None, &wrong))
+        .await;
+    assert_eq!(first.status, StatusCode::UNAUTHORIZED);
+    let retry = app
+        .send(keyed_post("/auth/login", "login-2", None, &wrong))
+        .await;
+    assert_eq!(retry.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(retry.headers[IDEMPOTENT_REPLAYED], "true");
+}
+
+#[tokio::test]
+async fn a_retry_of_a_running_request_waits() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice@example.com", false).await;
+    let body = json!({ "email": "alice@example.com", "password": PASSWORD });
+
+    // Claimed like the middleware does for a first request that has not answered yet.
+    let (parts, _) = keyed_post("/auth/login", "login-1", None, &body).into_parts();
+    let keys = app.idempotency.as_ref().unwrap();
+    let claim = keys
+        .claim(
+            &storage_key(&parts, "login-1"),
+            &fingerprint(&parts, body.to_string().as_bytes()),
+        )
+        .await
+        .unwrap();
+    assert!(matches!(claim, Claim::Acquired));
+
+    let response = app
+        .send(keyed_post("/auth/login", "login-1", None, &body))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "IDEMPOTENCY_KEY_IN_PROGRESS");
+    assert_eq!(response.headers[RETRY_AFTER], "1");
+}
@@ -0,0 +1,12 @@
+//! In-process tests of the API: each test gets the router of `build_app` over a schema of
+//! its own, migrated like `serve` does and dropped with the `TestApp`.
+//!
+//! Postgres is reached through `TEST_DATABASE_URL`, else `DATABASE_URL` (`.env` included),
+//! else the database of `docker compose up`.
+
+mod auth;
+mod common;
+mod health;
+mod idempotency;
+mod rate_limit;
+mod users;
@@ -0,0 +1,38 @@
+use axum::http::{Method, StatusCode};
+
+use crate::common::TestApp;
+
+#[tokio::test]
+async fn credentials_are_checked_once_the_address_is_admitted() {
+    let app = TestApp::spawn_with(|config| {
+        config.rate_limit.enabled = true;
+        config.rate_limit.requests_per_minute = 1;
+        config.rate_limit.burst = 3;
+        config.rate_limit.login_burst = 3;
+    })
+    .await;
+    app.create_user("alice@example.com", false).await;
+    let token = app.login("alice@example.com").await;
+
+    let forged = "00000000-0000-0000-0000-000000000000";
+    let response = app
+        .request(Method::POST, "/auth/me", Some(forged), None)
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .request(Method::POST, "/auth/me", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers["ratelimit-remaining"], "2");
+
+    // The bucket of the address is empty: even valid credentials wait, unchecked.
+    let response = app
+        .request(Method::POST, "/auth/me", Some(forged), None)
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .request(Method::POST, "/auth/me", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.code(), "RATE_LIMITED");
+}
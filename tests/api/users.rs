@@ -0,0 +1,346 @@
+use axum::body::Body;
+use axum::http::header::{CONTENT_TYPE, ETAG, IF_MATCH};
+use axum::http::{HeaderValue, Method, Request, StatusCode};
+use serde_json::json;
+
+use crate::common::{PASSWORD, SETUP_TOKEN, TestApp};
+
+fn root_user_request(setup_token: Option<&str>, email: &str) -> Request<Body> {
+    let mut request =
+        Request::post("/users/initial_root_user").header(CONTENT_TYPE, "application/json");
+    if let Some(setup_token) = setup_token {
+        request = request.header("x-setup-token", setup_token);
+    }
+    let body = json!({ "name": "root", "email": email, "password": PASSWORD });
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+#[tokio::test]
+async fn initial_root_user_needs_the_setup_token() {
+    let app = TestApp::spawn().await;
+
+    let response = app.send(root_user_request(None, "root@example.com")).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .send(root_user_request(Some("wrong-token"), "root@example.com"))
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.code(), "SETUP_TOKEN_INVALID");
+
+    let response = app
+        .send(root_user_request(Some(SETUP_TOKEN), "root@example.com"))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["email"], "root@example.com");
+    let token = app.login("root@example.com").await;
+    let response = app
+        .request(
+            Method::POST,
+            "/users/1/roles",
+            Some(&token),
+            Some(json!({ "name": "auditor" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "the root user is an admin");
+
+    // The token is spent, even for the root user itself.
+    for email in ["root@example.com", "mallory@example.com"] {
+        let response = app.send(root_user_request(Some(SETUP_TOKEN), email)).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.code(), "SETUP_UNAVAILABLE");
+    }
+}
+
+#[tokio::test]
+async fn list_users_filters_on_creation_date() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice@example.com", false).await;
+    app.create_user("bob@example.com", false).await;
+
+    let response = app.request(Method::GET, "/users", None, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let emails: Vec<_> = response
+        .json()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| user["email"].as_str().unwrap().to_owned())
+        .collect();
+    assert_eq!(emails, ["alice@example.com", "bob@example.com"]);
+
+    let response = app
+        .request(
+            Method::GET,
+            "/users?created_from=2999-01-01T00:00:00Z",
+            None,
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), json!([]));
+
+    let response = app
+        .request(Method::GET, "/users?created_from=yesterday", None, None)
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "INVALID_QUERY");
+}
+
+#[tokio::test]
+async fn create_user_needs_a_session_and_a_free_email() {
+    let app = TestApp::spawn().await;
+    app.create_user("alice@example.com", false).await;
+    let token = app.login("alice@example.com").await;
+    let bob = json!({ "name": "bob", "email": "Bob@Example.com", "password": PASSWORD });
+
+    let response = app
+        .request(Method::POST, "/users", None, Some(bob.clone()))
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .request(Method::POST, "/users", Some(&token), Some(bob.clone()))
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["email"], "bob@example.com");
+
+    let response = app
+        .request(Method::POST, "/users", Some(&token), Some(bob))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.code(), "VALIDATION_FAILED");
+}
+
+#[tokio::test]
+async fn get_user_returns_it_with_an_etag() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice@example.com", false).await;
+    app.create_user("bob@example.com", false).await;
+    let uri = format!("/users/{}", alice["id"]);
+
+    let response = app.request(Method::GET, &uri, None, None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.code(), "AUTH_MISSING_TOKEN");
+
+    let token = app.login("bob@example.com").await;
+    let response = app.request(Method::GET, &uri, Some(&token), None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["email"], "alice@example.com");
+    assert!(response.headers.contains_key(ETAG));
+
+    let response = app
+        .request(Method::GET, "/users/999", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.code(), "USER_NOT_FOUND");
+
+    let response = app
+        .request(Method::GET, "/users/0", Some(&token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn update_user_checks_the_version() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice@example.com", false).await;
+    app.create_user("admin@example.com", true).await;
+    let admin_token = app.login("admin@example.com").await;
+    let alice_token = app.login("alice@example.com").await;
+    let uri = format!("/users/{}", alice["id"]);
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &uri,
+            Some(&alice_token),
+            Some(json!({ "name": "a", "version": 1 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.code(), "AUTH_ADMIN_REQUIRED");
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &uri,
+            Some(&admin_token),
+            Some(json!({ "name": "a" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &uri,
+            Some(&admin_token),
+            Some(json!({ "name": "a", "version": 1 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["name"], "a");
+    assert_eq!(response.json()["version"], 2);
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &uri,
+            Some(&admin_token),
+            Some(json!({ "name": "b", "version": 1 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "VERSION_CONFLICT");
+    assert_eq!(response.json()["current"]["name"], "a");
+
+    let response = app
+        .request(
+            Method::PATCH,
+            &uri,
+            Some(&admin_token),
+            Some(json!({ "email": "admin@example.com", "version": 2 })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "USER_EMAIL_TAKEN");
+}
+
+#[tokio::test]
+async fn update_user_checks_if_match() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice@example.com", false).await;
+    app.create_user("admin@example.com", true).await;
+    let token = app.login("admin@example.com").await;
+    let uri = format!("/users/{}", alice["id"]);
+    let patch = |if_match: &str, body: serde_json::Value| {
+        Request::patch(&uri)
+            .header("authorization", format!("Bearer {}", token))
+            .header(CONTENT_TYPE, "application/json")
+            .header(IF_MATCH, if_match)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app.request(Method::GET, &uri, Some(&token), None).await;
+    let etag = response.headers[ETAG].to_str().unwrap().to_owned();
+
+    let response = app.send(patch("W/\"stale\"", json!({ "name": "a" }))).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "VERSION_CONFLICT");
+    assert_eq!(response.json()["current"]["version"], 1);
+
+    // A stale `If-Match` is not overridden by a current body version.
+    let response = app
+        .send(patch("W/\"stale\"", json!({ "name": "a", "version": 1 })))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let response = app.send(patch(&etag, json!({ "name": "a" }))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["version"], 2);
+
+    // The ETag read before the update no longer matches.
+    let response = app.send(patch(&etag, json!({ "name": "b" }))).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.json()["current"]["name"], "a");
+
+    let response = app.request(Method::GET, &uri, Some(&token), None).await;
+    let etag = response.headers[ETAG].to_str().unwrap().to_owned();
+    let response = app
+        .send(patch(&etag, json!({ "name": "b", "version": 1 })))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "VERSION_CONFLICT");
+    assert_eq!(response.json()["current"]["version"], 2);
+}
+
+#[tokio::test]
+async fn delete_user_honours_if_match() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice@example.com", false).await;
+    app.create_user("admin@example.com", true).await;
+    let token = app.login("admin@example.com").await;
+    let uri = format!("/users/{}", alice["id"]);
+
+    let request = Request::delete(&uri)
+        .header("authorization", format!("Bearer {}", token))
+        .header(IF_MATCH, "W/\"stale\"")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+
+    // An unreadable `If-Match` fails the precondition instead of being ignored.
+    let request = Request::delete(&uri)
+        .header("authorization", format!("Bearer {}", token))
+        .header(IF_MATCH, HeaderValue::from_bytes(b"\"caf\xe9\"").unwrap())
+        .body(Body::empty())
+        .unwrap();
+    let response = app.send(request).await;
+    assert_eq!(response.status, StatusCode::PRECONDITION_FAILED);
+
+    let response = app.request(Method::DELETE, &uri, Some(&token), None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["email"], "alice@example.com");
+
+    let response = app.request(Method::GET, &uri, Some(&token), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.request(Method::DELETE, &uri, Some(&token), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn roles_are_granted_and_revoked_by_admins() {
+    let app = TestApp::spawn().await;
+    let alice = app.create_user("alice@example.com", false).await;
+    app.create_user("admin@example.com", true).await;
+    let admin_token = app.login("admin@example.com").await;
+    let alice_token = app.login("alice@example.com").await;
+    let roles = format!("/users/{}/roles", alice["id"]);
+    let auditor = json!({ "name": "auditor", "description": "Reads the audit log" });
+
+    let response = app
+        .request(
+            Method::POST,
+            &roles,
+            Some(&alice_token),
+            Some(auditor.clone()),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app
+        .request(
+            Method::POST,
+            &roles,
+            Some(&admin_token),
+            Some(auditor.clone()),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["name"], "auditor");
+
+    let response = app
+        .request(Method::POST, &roles, Some(&admin_token), Some(auditor))
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.code(), "ROLE_ALREADY_GRANTED");
+
+    let auditor_role = format!("{}/auditor", roles);
+    let response = app
+        .request(Method::DELETE, &auditor_role, Some(&alice_token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app
+        .request(Method::DELETE, &auditor_role, Some(&admin_token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app
+        .request(Method::DELETE, &auditor_role, Some(&admin_token), None)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.code(), "ROLE_NOT_FOUND");
+}